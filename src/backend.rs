@@ -0,0 +1,488 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    hash::{BuildHasher, RandomState},
+    sync::{Arc, LazyLock, PoisonError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use sauce_api::source::{Output, Source, fuzzysearch::FuzzySearch, iqdb::Iqdb, saucenao::SauceNao};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{BackendSettings, Config},
+    error::BackendFailure,
+    i18n,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    SauceNao,
+    FuzzySearch,
+    Iqdb,
+}
+
+impl Backend {
+    pub const ALL: [Self; 3] = [Self::SauceNao, Self::FuzzySearch, Self::Iqdb];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::SauceNao => "SauceNao",
+            Self::FuzzySearch => "FuzzySearch",
+            Self::Iqdb => "IQDB",
+        }
+    }
+
+    async fn check(self, cfg: &Config, link: &str) -> Result<Output, SourceError> {
+        #[cfg(test)]
+        if let Some(searcher) = overridden(self) {
+            return searcher.search(link).await;
+        }
+
+        self.searcher(cfg).await?.search(link).await
+    }
+
+    /// The real source behind this backend.
+    pub(crate) async fn searcher(self, cfg: &Config) -> Result<Arc<dyn Searcher>, SourceError> {
+        let credentials = cfg.credentials();
+
+        Ok(match self {
+            Self::SauceNao => {
+                Arc::new(SauceNao::create(credentials.saucenao_api_key().clone()).await?)
+            }
+            Self::FuzzySearch => {
+                Arc::new(FuzzySearch::create(credentials.fuzzysearch_api_key().clone()).await?)
+            }
+            Self::Iqdb => Arc::new(Iqdb::create(()).await?),
+        })
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+pub type SourceError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An object-safe [`Source`], so that a backend can be swapped out.
+#[async_trait]
+pub trait Searcher: Send + Sync {
+    async fn search(&self, link: &str) -> Result<Output, SourceError>;
+}
+
+#[async_trait]
+impl<S: Source + Send + Sync> Searcher for S {
+    async fn search(&self, link: &str) -> Result<Output, SourceError> {
+        Ok(self.check(link).await?)
+    }
+}
+
+#[cfg(test)]
+static OVERRIDES: LazyLock<std::sync::RwLock<HashMap<Backend, Arc<dyn Searcher>>>> =
+    LazyLock::new(|| std::sync::RwLock::new(HashMap::new()));
+
+/// Makes every search on `backend` go through `searcher` instead.
+#[cfg(test)]
+pub fn set_override(backend: Backend, searcher: Arc<dyn Searcher>) {
+    OVERRIDES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .insert(backend, searcher);
+}
+
+#[cfg(test)]
+fn overridden(backend: Backend) -> Option<Arc<dyn Searcher>> {
+    OVERRIDES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get(&backend)
+        .cloned()
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    /// Every attempt ran past the configured timeout.
+    Timeout(Backend, Duration),
+    /// The circuit breaker is open, so the backend wasn't called at all.
+    Unavailable(Backend, Duration),
+    /// The last health check failed, so the backend wasn't called at all.
+    Down(Backend),
+    /// The operator turned the backend off with `/admin backend`.
+    Disabled(Backend),
+    Backend(Backend, SourceError),
+}
+
+impl SearchError {
+    pub const fn backend(&self) -> Backend {
+        match self {
+            Self::Timeout(backend, _)
+            | Self::Unavailable(backend, _)
+            | Self::Down(backend)
+            | Self::Disabled(backend)
+            | Self::Backend(backend, _) => *backend,
+        }
+    }
+
+    /// Whether trying again shortly has a chance of succeeding.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout(..) => true,
+            Self::Unavailable(..) | Self::Down(..) | Self::Disabled(..) => false,
+            Self::Backend(_, e) => matches!(
+                BackendFailure::classify(e),
+                BackendFailure::Timeout | BackendFailure::Down
+            ),
+        }
+    }
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(backend, timeout) => {
+                write!(f, "{backend} did not respond within {}s", timeout.as_secs())
+            }
+            Self::Unavailable(backend, remaining) => write!(
+                f,
+                "{backend} is disabled for another {}s after repeated failures",
+                remaining.as_secs()
+            ),
+            Self::Down(backend) => write!(f, "{backend} failed its last health check"),
+            Self::Disabled(backend) => write!(f, "{backend} was disabled by the operator"),
+            Self::Backend(backend, e) => write!(f, "{backend} returned an error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Backend(_, e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// What we currently know about a backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Status {
+    /// Result of the last health check, `None` if it hasn't been checked yet.
+    pub healthy: Option<bool>,
+    pub checked_at: Option<Instant>,
+    /// How long the last successful call took.
+    pub latency: Option<Duration>,
+    /// Set by the operator, overrides everything else.
+    pub disabled: bool,
+    consecutive_failures: u32,
+    failed_probes: u32,
+    open_until: Option<Instant>,
+}
+
+impl Status {
+    /// How long the circuit breaker keeps the backend disabled for.
+    pub fn disabled_for(&self) -> Option<Duration> {
+        self.open_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Whether the backend can be used, in `locale`.
+    pub fn summary(&self, locale: &str) -> String {
+        if self.disabled {
+            i18n::text(locale, "backend-disabled", &[])
+        } else if let Some(remaining) = self.disabled_for() {
+            i18n::text(
+                locale,
+                "backend-open",
+                &[("minutes", remaining.as_secs().div_ceil(60).into())],
+            )
+        } else if self.healthy == Some(false) {
+            match self.checked_at {
+                Some(at) => i18n::text(
+                    locale,
+                    "backend-down-checked",
+                    &[("minutes", (at.elapsed().as_secs() / 60).into())],
+                ),
+                None => i18n::text(locale, "backend-down", &[]),
+            }
+        } else if let Some(latency) = self.latency {
+            i18n::text(
+                locale,
+                "backend-latency",
+                &[("seconds", format!("{:.1}", latency.as_secs_f32()).into())],
+            )
+        } else {
+            i18n::text(locale, "backend-available", &[])
+        }
+    }
+}
+
+/// The longest wait between two attempts, however large the backoff gets.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long retrying one backend may go on for. With a search falling back
+/// through all three, that's still answered before Discord's 15 minute
+/// interaction token runs out.
+const RETRY_BUDGET: Duration = Duration::from_secs(4 * 60);
+
+static STATUS: LazyLock<RwLock<HashMap<Backend, Status>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub async fn status(backend: Backend) -> Status {
+    STATUS
+        .read()
+        .await
+        .get(&backend)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub async fn set_enabled(backend: Backend, enabled: bool) {
+    STATUS.write().await.entry(backend).or_default().disabled = !enabled;
+}
+
+/// Forgets health checks, latencies and open circuit breakers, keeping only
+/// what the operator set.
+pub async fn clear_status() {
+    for state in STATUS.write().await.values_mut() {
+        *state = Status {
+            disabled: state.disabled,
+            ..Status::default()
+        };
+    }
+}
+
+/// Fails early if `backend` is known to be down, so commands can bail before
+/// deferring.
+pub async fn ensure_available(backend: Backend) -> Result<(), SearchError> {
+    let status = status(backend).await;
+
+    if status.disabled {
+        return Err(SearchError::Disabled(backend));
+    }
+
+    if status.healthy == Some(false) {
+        return Err(SearchError::Down(backend));
+    }
+
+    if let Some(remaining) = status.disabled_for() {
+        return Err(SearchError::Unavailable(backend, remaining));
+    }
+
+    Ok(())
+}
+
+/// Runs a search against `backend`, with the timeout, retries and circuit
+/// breaker configured for it.
+pub async fn search(backend: Backend, cfg: &Config, link: &str) -> Result<Output, SearchError> {
+    let settings = cfg.settings().backend(backend);
+
+    ensure_available(backend).await?;
+
+    let started = Instant::now();
+    let mut attempt = 0;
+
+    let res = loop {
+        let start = Instant::now();
+        let res = match tokio::time::timeout(settings.timeout(), backend.check(cfg, link)).await {
+            Ok(Ok(output)) => Ok((output, start.elapsed())),
+            Ok(Err(e)) => Err(SearchError::Backend(backend, e)),
+            Err(_) => Err(SearchError::Timeout(backend, settings.timeout())),
+        };
+
+        match res {
+            Err(e) if e.is_transient() && attempt < settings.retries() => {
+                let delay = backoff(settings, attempt);
+                let next_done = started
+                    .elapsed()
+                    .saturating_add(delay)
+                    .saturating_add(settings.timeout());
+
+                if next_done > RETRY_BUDGET {
+                    debug!(%e, attempt, "Not retrying {backend}, as it's taken too long");
+
+                    break Err(e);
+                }
+
+                attempt += 1;
+
+                debug!(%e, ?delay, attempt, "Retrying {backend}");
+                tokio::time::sleep(delay).await;
+            }
+            res => break res,
+        }
+    };
+
+    match res {
+        Ok((output, latency)) => {
+            record_success(backend, latency).await;
+            count_search();
+
+            Ok(output)
+        }
+        // Bad input from the user says nothing about the backend's health.
+        Err(e) if e.is_transient() => {
+            record_failure(backend, settings).await;
+
+            Err(e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Successful searches as of midnight UTC, as the day they were on and how
+/// many there were.
+static SEARCHES_TODAY: std::sync::Mutex<(u64, u64)> = std::sync::Mutex::new((0, 0));
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() / 86_400)
+}
+
+fn count_search() {
+    let today = today();
+    let mut searches = SEARCHES_TODAY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    if searches.0 == today {
+        searches.1 += 1;
+    } else {
+        *searches = (today, 1);
+    }
+}
+
+/// How many searches succeeded since midnight UTC.
+pub fn searches_today() -> u64 {
+    let searches = SEARCHES_TODAY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    if searches.0 == today() { searches.1 } else { 0 }
+}
+
+async fn record_success(backend: Backend, latency: Duration) {
+    let mut states = STATUS.write().await;
+    let state = states.entry(backend).or_default();
+
+    state.consecutive_failures = 0;
+    state.open_until = None;
+    state.latency = Some(latency);
+    state.failed_probes = 0;
+
+    // A search getting through says more than a probe that didn't.
+    if state.healthy == Some(false) {
+        info!("Marking {backend} as available, as a search succeeded");
+
+        state.healthy = Some(true);
+    }
+}
+
+async fn record_failure(backend: Backend, settings: &BackendSettings) {
+    let mut states = STATUS.write().await;
+    let state = states.entry(backend).or_default();
+
+    state.consecutive_failures += 1;
+
+    if state.consecutive_failures >= settings.breaker_threshold() {
+        warn!(
+            failures = state.consecutive_failures,
+            "Disabling {backend} for {}s",
+            settings.breaker_cooldown().as_secs()
+        );
+
+        state.consecutive_failures = 0;
+        state.open_until = Some(Instant::now() + settings.breaker_cooldown());
+    }
+}
+
+/// Exponential backoff with up to one extra base delay of jitter, capped at
+/// [`MAX_BACKOFF`].
+pub fn backoff(settings: &BackendSettings, attempt: u8) -> Duration {
+    let base = settings.backoff().min(MAX_BACKOFF);
+    let millis = u64::try_from(base.as_millis()).unwrap_or(u64::MAX);
+    let jitter = RandomState::new().hash_one(Instant::now()) % millis.saturating_add(1);
+
+    base.checked_mul(2u32.saturating_pow(u32::from(attempt)))
+        .unwrap_or(MAX_BACKOFF)
+        .saturating_add(Duration::from_millis(jitter))
+        .min(MAX_BACKOFF)
+}
+
+/// Periodically searches for a known image on every backend listed in the
+/// health check settings, and marks each one as up or down accordingly. A
+/// failed probe is retried sooner, and only a few in a row mark a backend as
+/// down, so one blip doesn't take it out until the next round.
+pub async fn health_check() {
+    loop {
+        let cfg = Config::current();
+        let settings = cfg.settings().health_check();
+        let mut passed = true;
+
+        for &backend in settings.backends() {
+            passed &= probe(backend, &cfg, settings.probe_image()).await;
+        }
+
+        tokio::time::sleep(if passed {
+            settings.interval()
+        } else {
+            settings.retry()
+        })
+        .await;
+    }
+}
+
+/// Probes `backend`, returning whether it passed.
+async fn probe(backend: Backend, cfg: &Config, image: &str) -> bool {
+    let timeout = cfg.settings().backend(backend).timeout();
+    let start = Instant::now();
+    let res = tokio::time::timeout(timeout, backend.check(cfg, image)).await;
+    let latency = start.elapsed();
+
+    let healthy = matches!(res, Ok(Ok(_)));
+
+    match res {
+        Ok(Ok(_)) => debug!(?latency, "{backend} passed its health check"),
+        Ok(Err(e)) => warn!(?e, "{backend} failed its health check"),
+        Err(_) => warn!("{backend} timed out during its health check"),
+    }
+
+    let mut states = STATUS.write().await;
+    let state = states.entry(backend).or_default();
+
+    state.checked_at = Some(Instant::now());
+
+    if healthy {
+        if state.healthy == Some(false) {
+            info!("Marking {backend} as available");
+        }
+
+        state.healthy = Some(true);
+        state.failed_probes = 0;
+        state.consecutive_failures = 0;
+        state.open_until = None;
+        state.latency = Some(latency);
+    } else {
+        state.failed_probes = state.failed_probes.saturating_add(1);
+
+        if state.failed_probes >= cfg.settings().health_check().failures_before_down()
+            && state.healthy != Some(false)
+        {
+            info!(
+                failures = state.failed_probes,
+                "Marking {backend} as unavailable"
+            );
+
+            state.healthy = Some(false);
+        }
+    }
+
+    healthy
+}
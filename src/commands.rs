@@ -1,21 +1,58 @@
-use twilight_interactions::command::ApplicationCommandData;
+use twilight_interactions::command::{CommandOption, CreateOption};
 
+use crate::{backend::Backend, events::Entry};
+
+pub mod admin;
 pub mod basic;
+pub mod batch;
 pub mod fuzzysearch;
+pub mod help;
+pub mod history;
 pub mod iqdb;
 pub mod saucenao;
+pub mod status;
 
-pub fn get() -> Vec<ApplicationCommandData> {
-    let mut res = Vec::new();
-    let mut basic = basic::get();
-    let mut iqdb = iqdb::get();
-    let mut saucenao = saucenao::get();
-    let mut fuzzysearch = fuzzysearch::get();
+/// Every built-in command, as registered before any [`crate::events::register`].
+pub fn builtin() -> Vec<Entry> {
+    vec![
+        Entry::new::<help::HelpCommand>(),
+        Entry::new::<basic::IssueCommand>(),
+        Entry::new::<basic::SupportCommand>(),
+        Entry::new::<basic::InviteCommand>(),
+        Entry::new::<iqdb::Iqdb>(),
+        Entry::new::<saucenao::Saucenao>(),
+        Entry::new::<fuzzysearch::FuzzySearch>(),
+        Entry::new::<batch::Batch>(),
+        Entry::new::<status::StatusCommand>(),
+        Entry::new::<history::HistoryCommand>(),
+        Entry::new::<admin::AdminCommand>(),
+    ]
+}
 
-    res.append(&mut basic);
-    res.append(&mut iqdb);
-    res.append(&mut saucenao);
-    res.append(&mut fuzzysearch);
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy)]
+pub enum BackendOption {
+    #[option(name = "SauceNao", value = "saucenao")]
+    SauceNao,
+    #[option(name = "FuzzySearch", value = "fuzzysearch")]
+    FuzzySearch,
+    #[option(name = "IQDB", value = "iqdb")]
+    Iqdb,
+}
+
+impl From<BackendOption> for Backend {
+    fn from(value: BackendOption) -> Self {
+        match value {
+            BackendOption::SauceNao => Self::SauceNao,
+            BackendOption::FuzzySearch => Self::FuzzySearch,
+            BackendOption::Iqdb => Self::Iqdb,
+        }
+    }
+}
 
-    res
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreprocessOption {
+    #[option(name = "Crop borders and screenshot UI", value = "crop")]
+    Crop,
+    #[option(name = "Crop, then split collages into tiles", value = "split")]
+    Split,
 }
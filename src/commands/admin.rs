@@ -0,0 +1,339 @@
+use std::{fmt::Write as _, sync::Arc, time::UNIX_EPOCH};
+
+use async_trait::async_trait;
+use sparkle_convenience::Bot;
+use twilight_interactions::command::{CommandModel, CommandOption, CreateCommand, CreateOption};
+use twilight_model::{
+    guild::Permissions,
+    id::{Id, marker::UserMarker},
+};
+use twilight_util::builder::embed::EmbedBuilder;
+
+use crate::{
+    Res,
+    backend::{self, Backend},
+    commands::{BackendOption, saucenao},
+    config::{ActivityKind, Config, ConnectionMode, PresenceMessage, PresenceStatus},
+    error::{self, Friendly},
+    events::{self, Availability, Cmd, Command, Metadata},
+    handle::quiet_reply,
+    i18n, presence,
+};
+
+const fn admin_permissions() -> Permissions {
+    Permissions::ADMINISTRATOR
+}
+
+/// Whether `user` may run `/admin`: either listed in the config, or the owner
+/// (or a team member) of the application.
+fn is_owner(bot: &Bot, cfg: &Config, user: Id<UserMarker>) -> bool {
+    let application = &bot.application;
+
+    cfg.credentials().owners().contains(&user)
+        || application
+            .owner
+            .as_ref()
+            .is_some_and(|owner| owner.id == user)
+        || application
+            .team
+            .as_ref()
+            .is_some_and(|team| team.members.iter().any(|member| member.user.id == user))
+}
+
+// What's changed through `backend`, `ratelimit-set` and `presence` is only
+// kept in memory: it survives `reload`, but not a restart.
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "admin",
+    desc = "Commands for the bot's owner to manage it while it's running",
+    default_permissions = "admin_permissions"
+)]
+pub enum AdminCommand {
+    #[command(name = "reload")]
+    Reload(AdminReload),
+    #[command(name = "ratelimit-reset")]
+    RateLimitReset(AdminRateLimitReset),
+    #[command(name = "ratelimit-set")]
+    RateLimitSet(AdminRateLimitSet),
+    #[command(name = "backend")]
+    Backend(AdminBackend),
+    #[command(name = "register")]
+    Register(AdminRegister),
+    #[command(name = "clear-cache")]
+    ClearCache(AdminClearCache),
+    #[command(name = "errors")]
+    Errors(AdminErrors),
+    #[command(name = "presence")]
+    Presence(AdminPresence),
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "reload", desc = "Re-reads config.toml")]
+pub struct AdminReload;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "ratelimit-reset",
+    desc = "Gives the SauceNao rate limiters their full allowance back"
+)]
+pub struct AdminRateLimitReset;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "ratelimit-set",
+    desc = "Changes how many SauceNao searches are allowed per window"
+)]
+pub struct AdminRateLimitSet {
+    /// Searches allowed every 30 seconds
+    #[command(min_value = 0)]
+    short: Option<i64>,
+
+    /// Searches allowed every 24 hours
+    #[command(min_value = 0)]
+    long: Option<i64>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "backend", desc = "Turns a backend on or off")]
+pub struct AdminBackend {
+    /// The backend to change
+    backend: BackendOption,
+
+    /// Whether the backend should be used
+    enabled: bool,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "register",
+    desc = "Registers the slash commands with Discord again"
+)]
+pub struct AdminRegister;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "clear-cache",
+    desc = "Forgets backend health checks, latencies and circuit breakers"
+)]
+pub struct AdminClearCache;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "errors", desc = "Shows the most recent command failures")]
+pub struct AdminErrors;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "presence",
+    desc = "Shows a status of your choosing, or goes back to the configured ones"
+)]
+pub struct AdminPresence {
+    /// What to show, filling in {guilds}, {searches_today} and {saucenao_remaining}
+    #[command(max_length = 128)]
+    text: Option<String>,
+
+    /// What the bot is doing
+    activity: Option<ActivityOption>,
+
+    /// Whether the bot shows as online, idle, etc.
+    status: Option<StatusOption>,
+}
+
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityOption {
+    #[option(name = "Playing", value = "playing")]
+    Playing,
+    #[option(name = "Listening to", value = "listening")]
+    Listening,
+    #[option(name = "Watching", value = "watching")]
+    Watching,
+    #[option(name = "Competing in", value = "competing")]
+    Competing,
+}
+
+impl From<ActivityOption> for ActivityKind {
+    fn from(value: ActivityOption) -> Self {
+        match value {
+            ActivityOption::Playing => Self::Playing,
+            ActivityOption::Listening => Self::Listening,
+            ActivityOption::Watching => Self::Watching,
+            ActivityOption::Competing => Self::Competing,
+        }
+    }
+}
+
+#[derive(CommandOption, CreateOption, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusOption {
+    #[option(name = "Online", value = "online")]
+    Online,
+    #[option(name = "Idle", value = "idle")]
+    Idle,
+    #[option(name = "Do Not Disturb", value = "dnd")]
+    Dnd,
+    #[option(name = "Invisible", value = "invisible")]
+    Invisible,
+}
+
+impl From<StatusOption> for PresenceStatus {
+    fn from(value: StatusOption) -> Self {
+        match value {
+            StatusOption::Online => Self::Online,
+            StatusOption::Idle => Self::Idle,
+            StatusOption::Dnd => Self::Dnd,
+            StatusOption::Invisible => Self::Invisible,
+        }
+    }
+}
+
+#[async_trait]
+impl Cmd for AdminCommand {
+    fn metadata() -> Metadata {
+        // The bot's owner manages it from a server it was added to, where
+        // `default_permissions` applies too.
+        Metadata::new()
+            .defer()
+            .ephemeral()
+            .availability(Availability::InstalledGuildOnly)
+    }
+
+    async fn check(&self, bot: Arc<Bot>, command: &Command) -> Res<()> {
+        let cfg = Config::current();
+        let locale = command.handle.locale();
+
+        if !command
+            .author_id()
+            .is_some_and(|user| is_owner(&bot, &cfg, user))
+        {
+            return Err(Friendly::new(i18n::text(locale, "admin-owner-only", &[])).into());
+        }
+
+        if matches!(self, Self::Presence(_))
+            && cfg.settings().connection().mode() == ConnectionMode::Http
+        {
+            return Err(Friendly::new(i18n::text(locale, "admin-no-gateway", &[])).into());
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, bot: Arc<Bot>, command: Command) -> Res<()> {
+        let locale = command.handle.locale();
+
+        let reply = match self {
+            Self::Reload(_) => match Config::reload() {
+                Ok(_) => quiet_reply().content(i18n::text(locale, "admin-reloaded", &[])),
+                Err(e) => {
+                    let error: String = format!("{e:#}").chars().take(1800).collect();
+
+                    quiet_reply().content(format!(
+                        "{}\n```\n{error}\n```",
+                        i18n::text(locale, "admin-reload-failed", &[])
+                    ))
+                }
+            },
+            Self::RateLimitReset(_) => {
+                saucenao::reset_rate_limits().await;
+
+                quiet_reply().content(i18n::text(locale, "admin-rate-limits-reset", &[]))
+            }
+            Self::RateLimitSet(AdminRateLimitSet { short, long }) => {
+                let short = short.map(u64::try_from).transpose()?;
+                let long = long.map(u64::try_from).transpose()?;
+
+                saucenao::set_rate_limits(short, long).await;
+
+                let (short, long) = saucenao::quota().await;
+
+                quiet_reply().content(i18n::text(
+                    locale,
+                    "admin-rate-limits-set",
+                    &[("short", short.max().into()), ("long", long.max().into())],
+                ))
+            }
+            Self::Backend(AdminBackend { backend, enabled }) => {
+                let backend = Backend::from(*backend);
+
+                backend::set_enabled(backend, *enabled).await;
+
+                quiet_reply().content(i18n::text(
+                    locale,
+                    if *enabled {
+                        "admin-backend-enabled"
+                    } else {
+                        "admin-backend-disabled"
+                    },
+                    &[("backend", backend.to_string().into())],
+                ))
+            }
+            Self::Register(_) => {
+                events::ready(bot.clone()).await?;
+
+                quiet_reply().content(i18n::text(locale, "admin-registered", &[]))
+            }
+            Self::ClearCache(_) => {
+                backend::clear_status().await;
+
+                quiet_reply().content(i18n::text(locale, "admin-cache-cleared", &[]))
+            }
+            Self::Errors(_) => {
+                let mut description = String::new();
+
+                for error in error::recent() {
+                    let at = error
+                        .at
+                        .duration_since(UNIX_EPOCH)
+                        .map(|at| at.as_secs())
+                        .unwrap_or_default();
+                    let message: String = error.message.chars().take(120).collect();
+
+                    writeln!(
+                        description,
+                        "`{}` <t:{at}:R> `/{}`: {message}",
+                        error.correlation_id, error.command
+                    )?;
+                }
+
+                if description.is_empty() {
+                    description = i18n::text(locale, "admin-errors-none", &[]);
+                }
+
+                quiet_reply().embed(
+                    EmbedBuilder::new()
+                        .title(i18n::text(locale, "admin-errors-title", &[]))
+                        .description(description)
+                        .color(Config::current().branding().embed_color())
+                        .build(),
+                )
+            }
+            Self::Presence(AdminPresence {
+                text,
+                activity,
+                status,
+            }) => match text {
+                Some(text) => {
+                    let message = PresenceMessage {
+                        activity: activity.map(ActivityKind::from).unwrap_or_default(),
+                        text: text.clone(),
+                    };
+                    let shown = presence::render(&bot, &message.text).await;
+
+                    presence::set_override(Some((message, status.map(PresenceStatus::from))));
+
+                    quiet_reply().content(i18n::text(
+                        locale,
+                        "admin-presence-set",
+                        &[("presence", shown.into())],
+                    ))
+                }
+                None => {
+                    presence::set_override(None);
+
+                    quiet_reply().content(i18n::text(locale, "admin-presence-reset", &[]))
+                }
+            },
+        };
+
+        command.handle.reply(reply.update_last()).await?;
+
+        Ok(())
+    }
+}
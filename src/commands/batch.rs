@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use color_eyre::Report;
+use sparkle_convenience::Bot;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::Attachment;
+
+use crate::{
+    Res,
+    backend::Backend,
+    commands::BackendOption,
+    config::Config,
+    error::{self, Friendly},
+    events::{Cmd, Command, Metadata},
+    handle::quiet_reply,
+    history, i18n, recent, sauce_finder,
+};
+
+/// The most images searched for in one go.
+const MAX_IMAGES: usize = 10;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "batch",
+    desc = "Searches for several links or attachments at once, with one result per image."
+)]
+pub struct Batch {
+    /// The backend to search with
+    backend: BackendOption,
+
+    /// Links to search for, separated by spaces
+    links: Option<String>,
+
+    /// An attachment to search for
+    attachment: Option<Attachment>,
+
+    /// Another attachment to search for
+    attachment2: Option<Attachment>,
+
+    /// Another attachment to search for
+    attachment3: Option<Attachment>,
+
+    /// Another attachment to search for
+    attachment4: Option<Attachment>,
+
+    /// Whether the messages should be hidden
+    ephemeral: Option<bool>,
+}
+
+impl Batch {
+    /// Every link given, then every attachment, in order.
+    fn links(&self, locale: &str) -> Res<Vec<String>> {
+        let mut links = Vec::new();
+
+        for link in self.links.iter().flat_map(|links| links.split_whitespace()) {
+            links.push(sauce_finder::get_link_from_link(link.to_owned(), locale)?);
+        }
+
+        for attachment in [
+            &self.attachment,
+            &self.attachment2,
+            &self.attachment3,
+            &self.attachment4,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            links.push(sauce_finder::get_link_from_attachment(
+                attachment.clone(),
+                locale,
+            )?);
+        }
+
+        if links.is_empty() {
+            return Err(Friendly::new(i18n::text(locale, "no-image", &[])).into());
+        }
+
+        if links.len() > MAX_IMAGES {
+            return Err(Friendly::new(i18n::text(
+                locale,
+                "batch-too-many",
+                &[("count", links.len().into()), ("max", MAX_IMAGES.into())],
+            ))
+            .into());
+        }
+
+        Ok(links)
+    }
+}
+
+#[async_trait]
+impl Cmd for Batch {
+    fn metadata() -> Metadata {
+        sauce_finder::metadata()
+    }
+
+    fn ephemeral(&self) -> Option<bool> {
+        self.ephemeral
+    }
+
+    async fn check(&self, _bot: Arc<Bot>, command: &Command) -> Res<()> {
+        self.links(command.handle.locale())?;
+
+        sauce_finder::ensure_available(self.backend.into(), &Config::current()).await?;
+
+        Ok(())
+    }
+
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        let backend = Backend::from(self.backend);
+        let links = self.links(command.handle.locale())?;
+        let user = command.author_id();
+        let handle = command.handle;
+        let cfg = Config::current();
+
+        for (n, link) in links.into_iter().enumerate() {
+            if let Some(user) = user {
+                recent::searched(user, &link);
+            }
+
+            let (backend, fallbacks) =
+                match sauce_finder::route(&handle, backend, &cfg, self.ephemeral).await? {
+                    Ok(routed) => routed,
+                    Err(mut reply) => {
+                        // Only replace the "thinking..." message, not an earlier result.
+                        reply.update_last = n == 0;
+
+                        handle.reply(reply).await?;
+
+                        return Ok(());
+                    }
+                };
+
+            let res =
+                sauce_finder::search(&handle, backend, &fallbacks, &cfg, &link, self.ephemeral)
+                    .await;
+
+            match res {
+                Ok((backend, output)) => {
+                    history::record(user, backend, &link, &output);
+
+                    sauce_finder::respond(
+                        handle.clone(),
+                        Ok((backend, output)),
+                        cfg.clone(),
+                        self.ephemeral,
+                    )
+                    .await?;
+                }
+                // One image failing shouldn't stop the others from being searched.
+                Err(e) => {
+                    let reason = error::explain(&Report::new(e), handle.locale());
+                    let content = match sauce_finder::sanitize(&link) {
+                        Some(link) => i18n::text(
+                            handle.locale(),
+                            "batch-failed",
+                            &[
+                                ("number", (n + 1).into()),
+                                ("link", link.into()),
+                                ("reason", reason.into()),
+                            ],
+                        ),
+                        None => i18n::text(
+                            handle.locale(),
+                            "batch-failed-hidden",
+                            &[("number", (n + 1).into()), ("reason", reason.into())],
+                        ),
+                    };
+
+                    let mut reply = quiet_reply().content(content);
+
+                    if handle.ephemeral(self.ephemeral) {
+                        reply = reply.ephemeral();
+                    }
+
+                    handle.reply(reply).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,27 +1,18 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use sauce_api::source::{self, Source};
 use sparkle_convenience::Bot;
-use twilight_interactions::command::{ApplicationCommandData, CommandModel, CreateCommand};
-use twilight_model::{
-    channel::{Attachment, message::MessageFlags},
-    http::interaction::{InteractionResponse, InteractionResponseType},
-};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::Attachment;
 
 use crate::{
     Res,
-    config::Config,
-    events::{Cmd, Command},
-    handle::Handle,
-    handle::SpecialHandler,
-    sauce_finder,
+    backend::Backend,
+    commands::PreprocessOption,
+    events::{Cmd, Command, Metadata},
+    sauce_finder::{self, SearchOptions},
 };
 
-pub fn get() -> Vec<ApplicationCommandData> {
-    vec![FuzzySearch::create_command()]
-}
-
 #[derive(CommandModel, CreateCommand)]
 #[command(
     name = "fuzzysearch",
@@ -29,6 +20,7 @@ pub fn get() -> Vec<ApplicationCommandData> {
 )]
 pub struct FuzzySearch {
     /// The link to search for.
+    #[command(autocomplete = true)]
     link: Option<String>,
 
     /// An attachment to search for
@@ -36,56 +28,37 @@ pub struct FuzzySearch {
 
     /// Whether the message should be hidden
     ephemeral: Option<bool>,
+
+    /// Crops borders and screenshot UI off first, or splits a collage
+    preprocess: Option<PreprocessOption>,
 }
 
 impl FuzzySearch {
-    async fn execute_with_link(&self, handle: Handle, link: String) -> Res<()> {
-        let cfg = Config::load();
-        let source = source::fuzzysearch::FuzzySearch::create(
-            cfg.credentials().fuzzysearch_api_key().clone(),
-        )
-        .await
-        .expect("never fails");
-        let res = source.check(&link).await;
-
-        sauce_finder::respond(handle, res, cfg, self.ephemeral).await?;
-
-        Ok(())
+    const fn options(&self) -> SearchOptions<'_> {
+        SearchOptions {
+            link: self.link.as_ref(),
+            attachment: self.attachment.as_ref(),
+            ephemeral: self.ephemeral,
+            preprocess: self.preprocess,
+        }
     }
 }
 
 #[async_trait]
 impl Cmd for FuzzySearch {
-    async fn execute(&self, bot: Arc<Bot>, command: Command) -> Res<()> {
-        let handle = bot.handle(&command.interaction);
-        if self.link.is_none() && self.attachment.is_none() {
-            sauce_finder::respond_failure(handle).await?;
-            return Ok(());
-        }
+    fn metadata() -> Metadata {
+        sauce_finder::metadata().backend(Backend::FuzzySearch)
+    }
 
-        bot.http
-            .interaction(bot.application.id)
-            .create_response(
-                command.interaction_id,
-                &command.token,
-                &InteractionResponse {
-                    kind: InteractionResponseType::DeferredChannelMessageWithSource,
-                    data: Some(twilight_model::http::interaction::InteractionResponseData {
-                        flags: if self.ephemeral.unwrap_or_default() {
-                            Some(MessageFlags::EPHEMERAL)
-                        } else {
-                            None
-                        },
-                        ..Default::default()
-                    }),
-                },
-            )
-            .await?;
-        let link =
-            sauce_finder::get_link(&handle, self.link.as_ref(), self.attachment.as_ref()).await?;
+    fn ephemeral(&self) -> Option<bool> {
+        self.ephemeral
+    }
 
-        self.execute_with_link(handle, link).await?;
+    async fn check(&self, _bot: Arc<Bot>, command: &Command) -> Res<()> {
+        sauce_finder::check(Backend::FuzzySearch, &self.options(), command).await
+    }
 
-        Ok(())
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        sauce_finder::run(Backend::FuzzySearch, self.options(), command).await
     }
 }
@@ -0,0 +1,303 @@
+use std::{fmt::Write as _, sync::Arc};
+
+use async_trait::async_trait;
+use sparkle_convenience::Bot;
+use twilight_interactions::command::{ApplicationCommandData, CommandModel, CreateCommand};
+use twilight_model::{
+    application::command::{CommandOption, CommandOptionType},
+    channel::message::embed::{Embed, EmbedField},
+};
+use twilight_util::builder::embed::EmbedBuilder;
+
+use crate::{
+    Res,
+    backend::{self, Backend},
+    commands::{saucenao, status::format_duration},
+    config::Config,
+    error::Friendly,
+    events::{self, Availability, Cmd, Command, Metadata},
+    handle::quiet_reply,
+    i18n,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "help", desc = "Provides help for SauceBot")]
+pub struct HelpCommand {
+    /// A command to show the details of
+    #[command(autocomplete = true)]
+    command: Option<String>,
+}
+
+#[async_trait]
+impl Cmd for HelpCommand {
+    async fn check(&self, _bot: Arc<Bot>, command: &Command) -> Res<()> {
+        if let Some(name) = &self.command
+            && find(name).is_none()
+        {
+            return Err(Friendly::new(i18n::text(
+                command.handle.locale(),
+                "help-unknown-command",
+                &[("command", name.trim_start_matches('/').into())],
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        let locale = command.handle.locale();
+
+        let embed = match self.command.as_deref().and_then(find) {
+            Some((data, metadata)) => details(locale, &data, metadata).await,
+            None => overview(locale).await,
+        };
+
+        command
+            .handle
+            .reply(quiet_reply().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The registered commands anyone can use, translated, in order of name.
+fn visible() -> Vec<(ApplicationCommandData, Metadata)> {
+    events::commands()
+        .into_iter()
+        .filter(|(data, _)| {
+            data.default_member_permissions
+                .is_none_or(|permissions| permissions.is_empty())
+        })
+        .map(|(mut data, metadata)| {
+            i18n::localize(&mut data);
+
+            (data, metadata)
+        })
+        .collect()
+}
+
+fn find(name: &str) -> Option<(ApplicationCommandData, Metadata)> {
+    let name = name.trim_start_matches('/');
+
+    visible()
+        .into_iter()
+        .find(|(data, _)| data.name.eq_ignore_ascii_case(name))
+}
+
+/// Command names starting with `typed`, for autocompleting the `command`
+/// option.
+pub fn suggestions(typed: &str) -> Vec<String> {
+    let typed = typed.trim_start_matches('/').to_lowercase();
+
+    visible()
+        .into_iter()
+        .map(|(data, _)| data.name)
+        .filter(|name| name.starts_with(&typed))
+        .take(25)
+        .collect()
+}
+
+fn description<'a>(locale: &str, data: &'a ApplicationCommandData) -> &'a str {
+    i18n::pick(
+        &data.description,
+        data.description_localizations.as_ref(),
+        locale,
+    )
+}
+
+async fn overview(locale: &str) -> Embed {
+    let cfg = Config::current();
+    let settings = cfg.settings();
+
+    let mut commands = String::new();
+    for (data, _) in visible() {
+        let _ = writeln!(
+            commands,
+            "- `/{}`: {}",
+            data.name,
+            description(locale, &data)
+        );
+    }
+
+    let mut backends = String::new();
+    for backend in Backend::ALL {
+        let _ = writeln!(
+            backends,
+            "**{backend}**: {}",
+            backend_summary(locale, backend).await
+        );
+    }
+
+    EmbedBuilder::new()
+        .title(i18n::text(locale, "help-title", &[]))
+        .description(i18n::text(locale, "help-description", &[]))
+        .field(EmbedField {
+            name: i18n::text(locale, "help-commands", &[]),
+            value: commands,
+            inline: false,
+        })
+        .field(EmbedField {
+            name: i18n::text(locale, "help-backends", &[]),
+            value: backends,
+            inline: false,
+        })
+        .field(EmbedField {
+            name: i18n::text(locale, "help-settings", &[]),
+            value: i18n::text(
+                locale,
+                "help-settings-description",
+                &[
+                    ("top_links", settings.top_links().into()),
+                    (
+                        "retention_days",
+                        (settings.history().retention().as_secs() / 86_400).into(),
+                    ),
+                    ("max_entries", settings.history().max_entries().into()),
+                ],
+            ),
+            inline: false,
+        })
+        .color(cfg.branding().embed_color())
+        .build()
+}
+
+async fn details(locale: &str, data: &ApplicationCommandData, metadata: Metadata) -> Embed {
+    let mut embed = EmbedBuilder::new()
+        .title(format!("/{}", data.name))
+        .description(description(locale, data))
+        .color(Config::current().branding().embed_color());
+
+    let (subcommands, options): (Vec<_>, Vec<_>) = data.options.iter().partition(|option| {
+        matches!(
+            option.kind,
+            CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup
+        )
+    });
+
+    if !options.is_empty() {
+        embed = embed.field(EmbedField {
+            name: i18n::text(locale, "help-options", &[]),
+            value: describe_options(locale, &options),
+            inline: false,
+        });
+    }
+
+    if !subcommands.is_empty() {
+        let mut value = String::new();
+        for subcommand in subcommands {
+            let _ = writeln!(
+                value,
+                "- `/{} {}`: {}",
+                data.name,
+                subcommand.name,
+                i18n::pick(
+                    &subcommand.description,
+                    subcommand.description_localizations.as_ref(),
+                    locale,
+                )
+            );
+        }
+
+        embed = embed.field(EmbedField {
+            name: i18n::text(locale, "help-subcommands", &[]),
+            value,
+            inline: false,
+        });
+    }
+
+    let mut usage = vec![i18n::text(
+        locale,
+        match metadata.availability {
+            Availability::Anywhere => "help-anywhere",
+            Availability::GuildOnly => "help-guild-only",
+            Availability::DmOnly => "help-dm-only",
+            Availability::InstalledGuildOnly => "help-installed-guild-only",
+        },
+        &[],
+    )];
+
+    if let Some(cooldown) = metadata.cooldown {
+        usage.push(i18n::text(
+            locale,
+            "help-cooldown",
+            &[("cooldown", format_duration(cooldown).into())],
+        ));
+    }
+
+    if metadata.ephemeral {
+        usage.push(i18n::text(locale, "help-hidden", &[]));
+    }
+
+    embed = embed.field(EmbedField {
+        name: i18n::text(locale, "help-usage", &[]),
+        value: usage.join("\n"),
+        inline: false,
+    });
+
+    if let Some(backend) = metadata.backend {
+        embed = embed.field(EmbedField {
+            name: backend.to_string(),
+            value: backend_summary(locale, backend).await,
+            inline: false,
+        });
+    }
+
+    embed.build()
+}
+
+fn describe_options(locale: &str, options: &[&CommandOption]) -> String {
+    let mut res = String::new();
+
+    for option in options {
+        let _ = write!(
+            res,
+            "- `{}`: {}",
+            i18n::pick(&option.name, option.name_localizations.as_ref(), locale),
+            i18n::pick(
+                &option.description,
+                option.description_localizations.as_ref(),
+                locale,
+            )
+        );
+
+        if option.required == Some(true) {
+            let _ = write!(res, " ({})", i18n::text(locale, "help-required", &[]));
+        }
+
+        res.push('\n');
+    }
+
+    res
+}
+
+/// Whether `backend` is up, and how many searches the bot allows it.
+async fn backend_summary(locale: &str, backend: Backend) -> String {
+    let status = backend::status(backend).await.summary(locale);
+
+    let limits = if backend == Backend::SauceNao {
+        let (short, long) = saucenao::quota().await;
+
+        [short, long]
+            .iter()
+            .map(|limit| {
+                i18n::text(
+                    locale,
+                    "help-backend-limit",
+                    &[
+                        ("available", limit.available().into()),
+                        ("max", limit.max().into()),
+                        ("window", format_duration(limit.window()).into()),
+                        ("resets_in", format_duration(limit.resets_in()).into()),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n- ")
+    } else {
+        i18n::text(locale, "help-backend-unlimited", &[])
+    };
+
+    format!("{status}\n- {limits}")
+}
@@ -0,0 +1,258 @@
+use std::{fmt::Write as _, sync::Arc};
+
+use async_trait::async_trait;
+use sparkle_convenience::{Bot, interaction::DeferVisibility, reply::Reply};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{
+        Component,
+        component::{ActionRow, Button, ButtonStyle},
+    },
+    id::{Id, marker::UserMarker},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+use crate::{
+    Res,
+    config::Config,
+    error::Friendly,
+    events::{Cmd, Command},
+    handle::{Handle, quiet_reply},
+    history, i18n, recent, sauce_finder,
+};
+
+/// Searches shown per page, one re-run button each.
+const PER_PAGE: usize = 5;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "history",
+    desc = "Your past searches, if you've turned history on"
+)]
+pub enum HistoryCommand {
+    #[command(name = "list")]
+    List(HistoryList),
+    #[command(name = "on")]
+    On(HistoryOn),
+    #[command(name = "off")]
+    Off(HistoryOff),
+    #[command(name = "clear")]
+    Clear(HistoryClear),
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "list", desc = "Lists your past searches, newest first")]
+pub struct HistoryList {
+    /// The page to start on
+    #[command(min_value = 1)]
+    page: Option<i64>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "on", desc = "Starts keeping your searches")]
+pub struct HistoryOn;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "off",
+    desc = "Stops keeping your searches, without deleting them"
+)]
+pub struct HistoryOff;
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "clear", desc = "Deletes your search history, and turns it off")]
+pub struct HistoryClear;
+
+#[async_trait]
+impl Cmd for HistoryCommand {
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        let locale = command.handle.locale();
+        let user = command
+            .author_id()
+            .ok_or_else(|| Friendly::new(i18n::text(locale, "history-unknown-user", &[])))?;
+
+        let reply = match self {
+            Self::List(HistoryList { page }) => {
+                let page = page
+                    .and_then(|page| usize::try_from(page).ok())
+                    .unwrap_or(1);
+
+                list(locale, user, page.saturating_sub(1))
+            }
+            Self::On(_) => {
+                history::set_enabled(user, true);
+
+                quiet_reply().content(i18n::text(locale, "history-on", &[]))
+            }
+            Self::Off(_) => {
+                history::set_enabled(user, false);
+
+                quiet_reply().content(i18n::text(locale, "history-off", &[]))
+            }
+            Self::Clear(_) => {
+                history::clear(user);
+                recent::forget(user);
+
+                quiet_reply().content(i18n::text(locale, "history-cleared", &[]))
+            }
+        };
+
+        command.handle.reply(reply.ephemeral()).await?;
+
+        Ok(())
+    }
+}
+
+/// Page `page` (counting from zero) of `user`'s history, as an ephemeral reply
+/// that replaces the message its buttons are on.
+fn list(locale: &str, user: Id<UserMarker>, page: usize) -> Reply {
+    let entries = history::entries(user);
+    let reply = quiet_reply().ephemeral().update_last();
+
+    if entries.is_empty() {
+        let id = if history::is_enabled(user) {
+            "history-empty"
+        } else {
+            "history-disabled"
+        };
+
+        return reply.content(i18n::text(locale, id, &[]));
+    }
+
+    let pages = entries.len().div_ceil(PER_PAGE);
+    let page = page.min(pages - 1);
+    let shown = &entries[page * PER_PAGE..entries.len().min((page + 1) * PER_PAGE)];
+
+    let mut description = String::new();
+    for (n, entry) in shown.iter().enumerate() {
+        let _ = writeln!(
+            description,
+            "**{}.** <t:{}:R> {}: <{}>",
+            page * PER_PAGE + n + 1,
+            entry.at,
+            entry.backend,
+            entry.link
+        );
+
+        let top = entry.results.first().map_or_else(
+            || i18n::text(locale, "history-no-results", &[]),
+            |top| format!("{:0.2}% <{}>", top.similarity, top.link),
+        );
+        let _ = writeln!(description, "-# {top}");
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(i18n::text(locale, "history-title", &[]))
+        .description(description)
+        .footer(EmbedFooterBuilder::new(i18n::text(
+            locale,
+            "history-page",
+            &[("page", (page + 1).into()), ("pages", pages.into())],
+        )))
+        .color(Config::current().branding().embed_color())
+        .build();
+
+    let rerun = shown
+        .iter()
+        .enumerate()
+        .map(|(n, entry)| {
+            button(
+                format!("history:rerun:{}", entry.id),
+                i18n::text(
+                    locale,
+                    "history-rerun",
+                    &[("number", (page * PER_PAGE + n + 1).into())],
+                ),
+                sauce_finder::expired(&entry.link),
+            )
+        })
+        .collect();
+    let navigation = vec![
+        button(
+            format!("history:page:{}", page.saturating_sub(1)),
+            i18n::text(locale, "history-previous", &[]),
+            page == 0,
+        ),
+        button(
+            format!("history:page:{}", page + 1),
+            i18n::text(locale, "history-next", &[]),
+            page + 1 >= pages,
+        ),
+    ];
+
+    let mut reply = reply.embed(embed);
+    reply.components = vec![
+        Component::ActionRow(ActionRow { components: rerun }),
+        Component::ActionRow(ActionRow {
+            components: navigation,
+        }),
+    ];
+
+    reply
+}
+
+fn button(custom_id: String, label: String, disabled: bool) -> Component {
+    Component::Button(Button {
+        custom_id: Some(custom_id),
+        disabled,
+        emoji: None,
+        label: Some(label),
+        style: ButtonStyle::Secondary,
+        url: None,
+        sku_id: None,
+    })
+}
+
+/// Handles the buttons under `/history list`, with `id` being the custom ID
+/// after `history:`.
+pub async fn component(handle: Handle, user: Option<Id<UserMarker>>, id: &str) -> Res<()> {
+    let locale = handle.locale();
+    let user =
+        user.ok_or_else(|| Friendly::new(i18n::text(locale, "history-unknown-presser", &[])))?;
+
+    match id.split_once(':') {
+        Some(("page", page)) => {
+            handle.reply(list(locale, user, page.parse()?)).await?;
+        }
+        Some(("rerun", entry)) => {
+            let entry = history::entry(user, entry.parse()?)
+                .ok_or_else(|| Friendly::new(i18n::text(locale, "history-gone", &[])))?;
+
+            if sauce_finder::expired(&entry.link) {
+                return Err(Friendly::new(i18n::text(locale, "history-expired", &[])).into());
+            }
+
+            rerun(handle, user, entry).await?;
+        }
+        _ => return Err(Friendly::new(i18n::text(locale, "history-stale-button", &[])).into()),
+    }
+
+    Ok(())
+}
+
+async fn rerun(handle: Handle, user: Id<UserMarker>, entry: history::Entry) -> Res<()> {
+    let cfg = Config::current();
+
+    sauce_finder::ensure_available(entry.backend, &cfg).await?;
+
+    handle.defer(DeferVisibility::Ephemeral).await?;
+
+    let (backend, fallbacks) =
+        match sauce_finder::route(&handle, entry.backend, &cfg, Some(true)).await? {
+            Ok(routed) => routed,
+            Err(reply) => {
+                handle.reply(reply).await?;
+
+                return Ok(());
+            }
+        };
+
+    let res =
+        sauce_finder::search(&handle, backend, &fallbacks, &cfg, &entry.link, Some(true)).await;
+
+    if let Ok((backend, output)) = &res {
+        history::record(Some(user), *backend, &entry.link, output);
+    }
+
+    sauce_finder::respond(handle, res, cfg, Some(true)).await
+}
@@ -1,23 +1,17 @@
-#![allow(dead_code)] // TODO: Fix when IQDB isn't broken :\
-
 use std::sync::Arc;
 
 use crate::{
-    config::Config,
-    events::{Cmd, Command},
-    handle::{Handle, SpecialHandler},
-    sauce_finder, Res,
+    Res,
+    backend::Backend,
+    commands::PreprocessOption,
+    events::{Cmd, Command, Metadata},
+    sauce_finder::{self, SearchOptions},
 };
 use async_trait::async_trait;
-use sauce_api::source::{self, Source};
-use sparkle_convenience::{reply::Reply, Bot};
-use twilight_interactions::command::{ApplicationCommandData, CommandModel, CreateCommand};
+use sparkle_convenience::Bot;
+use twilight_interactions::command::{CommandModel, CreateCommand};
 use twilight_model::channel::Attachment;
 
-pub fn get() -> Vec<ApplicationCommandData> {
-    vec![Iqdb::create_command()]
-}
-
 #[derive(CommandModel, CreateCommand)]
 #[command(
     name = "iqdb",
@@ -25,6 +19,7 @@ pub fn get() -> Vec<ApplicationCommandData> {
 )]
 pub struct Iqdb {
     /// The link to search for.
+    #[command(autocomplete = true)]
     link: Option<String>,
 
     /// An attachment to search for
@@ -32,52 +27,37 @@ pub struct Iqdb {
 
     /// Whether the message should be hidden
     ephemeral: Option<bool>,
+
+    /// Crops borders and screenshot UI off first, or splits a collage
+    preprocess: Option<PreprocessOption>,
 }
 
 impl Iqdb {
-    async fn execute_with_link(&self, handle: Handle, link: String) -> Res<()> {
-        let cfg = Config::load();
-        let source = source::iqdb::Iqdb::create(()).await.expect("never fails");
-        let res = source.check(&link).await;
-
-        sauce_finder::respond(handle, res, cfg, self.ephemeral).await?;
-
-        Ok(())
+    const fn options(&self) -> SearchOptions<'_> {
+        SearchOptions {
+            link: self.link.as_ref(),
+            attachment: self.attachment.as_ref(),
+            ephemeral: self.ephemeral,
+            preprocess: self.preprocess,
+        }
     }
 }
 
 #[async_trait]
 impl Cmd for Iqdb {
-    async fn execute(&self, bot: Arc<Bot>, command: Command) -> Res<()> {
-        let handle = bot.handle(&command.interaction);
-
-        handle
-            .reply(
-                Reply::new()
-                    .content("IQDB is currently broken :/")
-                    .ephemeral(),
-            )
-            .await?;
-
-        return Ok(());
-
-        // if self.link.is_none() && self.attachment.is_none() {
-        //     sauce_finder::respond_failure(handle).await?;
-        //     return Ok(());
-        // }
-
-        // handle
-        //     .defer(if self.ephemeral.unwrap_or(false) {
-        //         DeferVisibility::Ephemeral
-        //     } else {
-        //         DeferVisibility::Visible
-        //     })
-        //     .await?;
+    fn metadata() -> Metadata {
+        sauce_finder::metadata().backend(Backend::Iqdb)
+    }
 
-        // let link = sauce_finder::get_link(&handle, &self.link, &self.attachment).await?;
+    fn ephemeral(&self) -> Option<bool> {
+        self.ephemeral
+    }
 
-        // self.execute_with_link(handle, link).await?;
+    async fn check(&self, _bot: Arc<Bot>, command: &Command) -> Res<()> {
+        sauce_finder::check(Backend::Iqdb, &self.options(), command).await
+    }
 
-        // Ok(())
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        sauce_finder::run(Backend::Iqdb, self.options(), command).await
     }
 }
@@ -1,29 +1,31 @@
 use std::{
-    sync::{Arc, LazyLock},
+    collections::VecDeque,
+    sync::{
+        Arc, LazyLock, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
     time::Duration,
 };
 
 use crate::{
     Res,
+    backend::Backend,
+    commands::PreprocessOption,
     config::Config,
-    events::{Cmd, Command},
-    handle::{Handle, SpecialHandler},
+    events::{Cmd, Command, Metadata},
+    handle::Handle,
+    i18n,
     rate_limiter::RateLimiter,
-    sauce_finder,
+    sauce_finder::{self, SearchOptions},
 };
 use async_trait::async_trait;
-use sauce_api::source::{Source, saucenao::SauceNao};
 use sparkle_convenience::{Bot, reply::Reply};
-use tokio::sync::RwLock;
-use twilight_interactions::command::{ApplicationCommandData, CommandModel, CreateCommand};
-use twilight_model::{
-    channel::{Attachment, message::MessageFlags},
-    http::interaction::{InteractionResponse, InteractionResponseType},
+use tokio::{
+    sync::{Notify, RwLock},
+    time::{Instant, sleep, sleep_until},
 };
-
-pub fn get() -> Vec<ApplicationCommandData> {
-    vec![Saucenao::create_command()]
-}
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::Attachment;
 
 #[derive(Debug, Clone)]
 struct RateLimits {
@@ -38,15 +40,20 @@ enum Cause {
 }
 
 impl RateLimits {
+    /// Uses up a search from both windows, unless either has none left.
     pub fn limited(&mut self) -> bool {
-        let a = self.short_usage.attempt();
-        let b = self.long_usage.attempt();
+        if self.short_usage.available() == 0 || self.long_usage.available() == 0 {
+            return true;
+        }
 
-        !(a || b)
+        self.short_usage.attempt();
+        self.long_usage.attempt();
+
+        false
     }
 
-    pub const fn cause(&self) -> Cause {
-        if self.short_usage.remaining() == 0 {
+    pub fn cause(&self) -> Cause {
+        if self.long_usage.available() == 0 {
             Cause::Long
         } else {
             Cause::Short
@@ -64,6 +71,198 @@ impl RateLimits {
 static RATE_LIMITS: LazyLock<Arc<RwLock<RateLimits>>> =
     LazyLock::new(|| Arc::new(RwLock::new(RateLimits::new())));
 
+pub async fn reset_rate_limits() {
+    let mut rate_limits = RATE_LIMITS.write().await;
+
+    rate_limits.short_usage.reset();
+    rate_limits.long_usage.reset();
+}
+
+pub async fn set_rate_limits(short: Option<u64>, long: Option<u64>) {
+    let mut rate_limits = RATE_LIMITS.write().await;
+
+    if let Some(short) = short {
+        rate_limits.short_usage.set_max(short);
+    }
+
+    if let Some(long) = long {
+        rate_limits.long_usage.set_max(long);
+    }
+}
+
+/// A snapshot of the short and long SauceNao rate limiters, in that order.
+pub async fn quota() -> (RateLimiter, RateLimiter) {
+    let rate_limits = RATE_LIMITS.read().await;
+
+    (
+        rate_limits.short_usage.clone(),
+        rate_limits.long_usage.clone(),
+    )
+}
+
+/// Searches waiting for the short window, by ticket, oldest first.
+static QUEUE: Mutex<VecDeque<u64>> = Mutex::new(VecDeque::new());
+
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(0);
+
+/// Woken whenever a search leaves the queue.
+static MOVED: Notify = Notify::const_new();
+
+/// Takes a search out of the queue when it's dropped, however the wait ends.
+struct Ticket(u64);
+
+impl Ticket {
+    /// Joins the back of the queue, unless it already has `max_length`
+    /// searches waiting.
+    fn join(max_length: usize) -> Option<Self> {
+        let mut queue = QUEUE.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if queue.len() >= max_length {
+            return None;
+        }
+
+        let ticket = NEXT_TICKET.fetch_add(1, Ordering::Relaxed);
+        queue.push_back(ticket);
+
+        Some(Self(ticket))
+    }
+
+    /// Where the search is in the queue, starting from 1.
+    fn position(&self) -> usize {
+        QUEUE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .position(|&ticket| ticket == self.0)
+            .map_or(0, |index| index + 1)
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        QUEUE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|&ticket| ticket != self.0);
+
+        MOVED.notify_waiters();
+    }
+}
+
+/// Uses up a search from the rate limiters, returning why there are none
+/// left if so. Searches that aren't `first_in_line` don't get ahead of the
+/// queue.
+async fn try_take(first_in_line: bool) -> Option<Cause> {
+    if !first_in_line
+        && !QUEUE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    {
+        return Some(Cause::Short);
+    }
+
+    let mut rate_limits = RATE_LIMITS.write().await;
+
+    rate_limits.limited().then(|| rate_limits.cause())
+}
+
+fn limited_reply(locale: &str, cause: Cause) -> Reply {
+    let message = match cause {
+        Cause::Short => "saucenao-limited-short",
+        Cause::Long => "saucenao-limited-long",
+    };
+
+    Reply::new()
+        .content(i18n::text(locale, message, &[]))
+        .ephemeral()
+        .update_last()
+}
+
+/// Uses up a search from the rate limiters, if there's one left.
+pub async fn try_take_search() -> bool {
+    try_take(false).await.is_none()
+}
+
+/// Like [`try_take_search`], but waits in the queue for the short window if
+/// there's room, keeping the user posted on where they are in it. Otherwise
+/// explains why there are no searches left.
+pub async fn queue_search(handle: &Handle, ephemeral: Option<bool>) -> Res<Option<Reply>> {
+    let locale = handle.locale();
+
+    let Some(cause) = try_take(false).await else {
+        return Ok(None);
+    };
+
+    let cfg = Config::current();
+    let settings = cfg.settings().queue();
+
+    let ticket = match cause {
+        Cause::Short => Ticket::join(settings.max_length()),
+        Cause::Long => None,
+    };
+    let Some(ticket) = ticket else {
+        return Ok(Some(limited_reply(locale, cause)));
+    };
+
+    let deadline = Instant::now() + settings.max_wait();
+    let mut shown = None;
+
+    loop {
+        // Made before looking at the queue, so a move in between isn't missed.
+        let moved = MOVED.notified();
+        let position = ticket.position();
+
+        if position == 1 {
+            match try_take(true).await {
+                None => {
+                    if shown.is_some() {
+                        handle
+                            .reply(
+                                Reply::new()
+                                    .content(i18n::text(locale, "saucenao-queue-searching", &[]))
+                                    .update_last(),
+                            )
+                            .await?;
+                    }
+
+                    return Ok(None);
+                }
+                Some(Cause::Long) => return Ok(Some(limited_reply(locale, Cause::Long))),
+                Some(Cause::Short) => {}
+            }
+        }
+
+        if shown != Some(position) {
+            let mut reply = Reply::new().content(i18n::text(
+                locale,
+                "saucenao-queued",
+                &[("position", position.into())],
+            ));
+
+            // The first update is its own message, so it doesn't replace an
+            // earlier result. Later ones change it in place.
+            if shown.is_some() {
+                reply = reply.update_last();
+            } else if handle.ephemeral(ephemeral) {
+                reply = reply.ephemeral();
+            }
+
+            handle.reply(reply).await?;
+            shown = Some(position);
+        }
+
+        let (short, _) = quota().await;
+        let resets_in = short.resets_in().max(Duration::from_secs(1));
+
+        tokio::select! {
+            () = sleep_until(deadline) => return Ok(Some(limited_reply(locale, Cause::Short))),
+            () = sleep(resets_in) => {}
+            () = moved => {}
+        }
+    }
+}
+
 #[derive(CreateCommand, CommandModel)]
 #[command(
     name = "saucenao",
@@ -71,6 +270,7 @@ static RATE_LIMITS: LazyLock<Arc<RwLock<RateLimits>>> =
 )]
 pub struct Saucenao {
     /// The link to search for.
+    #[command(autocomplete = true)]
     link: Option<String>,
 
     /// An attachment to search for
@@ -78,81 +278,37 @@ pub struct Saucenao {
 
     /// Whether the message should be hidden
     ephemeral: Option<bool>,
+
+    /// Crops borders and screenshot UI off first, or splits a collage
+    preprocess: Option<PreprocessOption>,
 }
 
 impl Saucenao {
-    async fn execute_with_link(&self, handle: Handle, link: String) -> Res<()> {
-        let cfg = Config::load();
-        let source = SauceNao::create(cfg.credentials().saucenao_api_key().clone())
-            .await
-            .expect("never fails");
-        let res = source.check(&link).await;
-
-        sauce_finder::respond(handle, res, cfg, self.ephemeral).await?;
-
-        Ok(())
+    const fn options(&self) -> SearchOptions<'_> {
+        SearchOptions {
+            link: self.link.as_ref(),
+            attachment: self.attachment.as_ref(),
+            ephemeral: self.ephemeral,
+            preprocess: self.preprocess,
+        }
     }
 }
 
 #[async_trait]
 impl Cmd for Saucenao {
-    async fn execute(&self, bot: Arc<Bot>, command: Command) -> Res<()> {
-        let mut rate_limits = RATE_LIMITS.write().await;
-
-        let handle = bot.handle(&command.interaction);
-
-        bot.http
-            .interaction(bot.application.id)
-            .create_response(
-                command.interaction_id,
-                &command.token,
-                &InteractionResponse {
-                    kind: InteractionResponseType::DeferredChannelMessageWithSource,
-                    data: Some(twilight_model::http::interaction::InteractionResponseData {
-                        flags: if self.ephemeral.unwrap_or_default() {
-                            Some(MessageFlags::EPHEMERAL)
-                        } else {
-                            None
-                        },
-                        ..Default::default()
-                    }),
-                },
-            )
-            .await?;
-
-        let cause = if rate_limits.limited() {
-            Some(rate_limits.cause())
-        } else {
-            None
-        };
-
-        drop(rate_limits);
-
-        if let Some(cause) = cause {
-            let reply = match cause {
-                Cause::Short => Reply::new()
-                    .content("You are being rate limited. Please wait up to 30 seconds before trying again. (sorry, the rate limits on SauceNao are like this. Consider `/support`ing the bot's creator)".to_owned())
-                    .ephemeral(),
-                Cause::Long => Reply::new()
-                    .content("You are being rate limited. Please wait up to 24 hours for it to fix. (sorry, the rate limits on SauceNao are like this. Consider `/support`ing the bot's creator)".to_owned())
-                    .ephemeral(),
-            };
-
-            handle.reply(reply).await?;
-
-            return Ok(());
-        }
-
-        if self.link.is_none() && self.attachment.is_none() {
-            sauce_finder::respond_failure(handle).await?;
-            return Ok(());
-        }
+    fn metadata() -> Metadata {
+        sauce_finder::metadata().backend(Backend::SauceNao)
+    }
 
-        let link =
-            sauce_finder::get_link(&handle, self.link.as_ref(), self.attachment.as_ref()).await?;
+    fn ephemeral(&self) -> Option<bool> {
+        self.ephemeral
+    }
 
-        self.execute_with_link(handle, link).await?;
+    async fn check(&self, _bot: Arc<Bot>, command: &Command) -> Res<()> {
+        sauce_finder::check(Backend::SauceNao, &self.options(), command).await
+    }
 
-        Ok(())
+    async fn execute(&self, _bot: Arc<Bot>, command: Command) -> Res<()> {
+        sauce_finder::run(Backend::SauceNao, self.options(), command).await
     }
 }
@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use sparkle_convenience::Bot;
+use tokio::sync::RwLock;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::message::embed::EmbedField;
+use twilight_util::builder::embed::EmbedBuilder;
+
+use crate::{
+    Res,
+    backend::{self, Backend},
+    commands::saucenao,
+    config::{Config, ConnectionMode},
+    events::{Cmd, Command},
+    handle::quiet_reply,
+    i18n, presence, recent,
+};
+
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+static SHARD_LATENCY: LazyLock<RwLock<BTreeMap<u32, Duration>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// Starts the uptime clock.
+pub fn mark_started() {
+    LazyLock::force(&STARTED_AT);
+}
+
+pub async fn record_shard_latency(shard: u32, latency: Option<Duration>) {
+    if let Some(latency) = latency {
+        SHARD_LATENCY.write().await.insert(shard, latency);
+    }
+}
+
+/// Drops the latencies of shards that were replaced by resharding.
+pub async fn forget_shard_latency() {
+    SHARD_LATENCY.write().await.clear();
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "status",
+    desc = "Shows whether the backends are up, and how much SauceNao quota is left"
+)]
+pub struct StatusCommand;
+
+#[async_trait]
+impl Cmd for StatusCommand {
+    async fn execute(&self, bot: Arc<Bot>, command: Command) -> Res<()> {
+        let locale = command.handle.locale();
+        let field = |name: &str, value: String, inline: bool| EmbedField {
+            name: i18n::text(locale, name, &[]),
+            value,
+            inline,
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title(i18n::text(locale, "status-title", &[]))
+            .color(Config::current().branding().embed_color());
+
+        for backend in Backend::ALL {
+            embed = embed.field(EmbedField {
+                name: backend.to_string(),
+                value: backend::status(backend).await.summary(locale),
+                inline: false,
+            });
+        }
+
+        let (short, long) = saucenao::quota().await;
+
+        embed = embed.field(field(
+            "status-quota",
+            i18n::text(
+                locale,
+                "status-quota-value",
+                &[
+                    ("short_available", short.available().into()),
+                    ("short_max", short.max().into()),
+                    ("short_resets_in", format_duration(short.resets_in()).into()),
+                    ("long_available", long.available().into()),
+                    ("long_max", long.max().into()),
+                    ("long_resets_in", format_duration(long.resets_in()).into()),
+                ],
+            ),
+            false,
+        ));
+
+        let guilds = presence::guild_count(&bot).await.map_or_else(
+            || i18n::text(locale, "status-servers-unknown", &[]),
+            |count| count.to_string(),
+        );
+
+        let mut shards = String::new();
+        for (shard, latency) in SHARD_LATENCY.read().await.iter() {
+            writeln!(
+                shards,
+                "{}",
+                i18n::text(
+                    locale,
+                    "status-shard",
+                    &[
+                        ("shard", (*shard).into()),
+                        ("latency", latency.as_millis().to_string().into()),
+                    ],
+                )
+            )?;
+        }
+        if shards.is_empty() {
+            let id = match Config::current().settings().connection().mode() {
+                ConnectionMode::Gateway => "status-no-heartbeats",
+                ConnectionMode::Http => "status-no-shards",
+            };
+
+            shards = i18n::text(locale, id, &[]);
+        }
+
+        let (users, searched) = recent::searched_size();
+        let mut cache = i18n::text(
+            locale,
+            "status-cache-searched",
+            &[("links", searched.into()), ("users", users.into())],
+        );
+        if Config::current().settings().cache_messages() {
+            let (channels, posted) = recent::posted_size();
+
+            cache.push('\n');
+            cache.push_str(&i18n::text(
+                locale,
+                "status-cache-posted",
+                &[("links", posted.into()), ("channels", channels.into())],
+            ));
+        }
+
+        let embed = embed
+            .field(field(
+                "status-uptime",
+                format_duration(STARTED_AT.elapsed()),
+                true,
+            ))
+            .field(field("status-servers", guilds, true))
+            .field(field("status-cache", cache, false))
+            .field(field("status-shard-latency", shards, false))
+            .build();
+
+        command
+            .handle
+            .reply(quiet_reply().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, secs) =
+        (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {secs}s")
+    } else {
+        format!("{secs}s")
+    }
+}
@@ -1,45 +1,107 @@
-use std::{fs::OpenOptions, io::Read, io::Write, path::PathBuf};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{OnceLock, PoisonError, RwLock},
+    time::Duration,
+};
 
+use color_eyre::eyre::WrapErr as _;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
+use twilight_model::{
+    gateway::presence::{ActivityType, Status},
+    id::{Id, marker::UserMarker},
+};
+
+use crate::{Res, backend::Backend};
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Config {
     credentials: Credentials,
     settings: Settings,
+    #[serde(default)]
+    branding: Branding,
 }
 
+static CURRENT: RwLock<Option<Config>> = RwLock::new(None);
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
 impl Config {
     fn get_path() -> PathBuf {
-        if option_env!("CONTAINER") == Some("true") {
+        if let Some(path) = PATH.get() {
+            path.clone()
+        } else if option_env!("CONTAINER") == Some("true") {
             PathBuf::from("/config/config.toml")
         } else {
             PathBuf::from("./config.toml")
         }
     }
 
-    pub const fn credentials(&self) -> &Credentials {
+    /// Where the bot keeps `name`, next to the config.
+    pub fn data_path(name: &str) -> PathBuf {
+        Self::get_path().with_file_name(name)
+    }
+
+    /// Reads and writes the config at `path` instead of the default location.
+    /// Only has an effect before the config is first loaded.
+    pub fn use_path(path: impl Into<PathBuf>) {
+        let _ = PATH.set(path.into());
+    }
+
+    pub(crate) const fn credentials(&self) -> &Credentials {
         &self.credentials
     }
 
-    pub const fn settings(&self) -> &Settings {
+    pub(crate) const fn settings(&self) -> &Settings {
         &self.settings
     }
 
-    pub fn load() -> Self {
-        let path = Self::get_path();
+    pub(crate) const fn branding(&self) -> &Branding {
+        &self.branding
+    }
 
-        if path.exists() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(path)
-                .expect("Unable to open `config.toml` for reading. Please check permissions");
+    /// The config as of startup or the last [`Self::reload`], loading it if
+    /// that hasn't happened yet.
+    pub fn current() -> Self {
+        if let Some(cfg) = CURRENT
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return cfg.clone();
+        }
+
+        let cfg = Self::load();
 
-            let mut content = String::new();
-            file.read_to_string(&mut content)
-                .expect("Unable to read `config.toml`");
+        CURRENT
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert(cfg)
+            .clone()
+    }
+
+    /// Makes `cfg` the current config, without touching `config.toml`.
+    pub fn set_current(cfg: Self) {
+        *CURRENT.write().unwrap_or_else(PoisonError::into_inner) = Some(cfg);
+    }
+
+    /// Re-reads `config.toml` and makes it the current config. If it can't
+    /// be read, the current config is kept.
+    pub fn reload() -> Res<Self> {
+        let cfg = Self::read()?;
+
+        Self::set_current(cfg.clone());
+
+        Ok(cfg)
+    }
 
-            toml::from_str(&content).expect("Unable to parse `config.toml`")
+    /// Reads `config.toml`, writing out the default one first if there isn't
+    /// one yet.
+    pub fn load() -> Self {
+        if Self::get_path().exists() {
+            Self::read().expect("Unable to load `config.toml`")
         } else {
             let cfg = Self::default();
             cfg.save();
@@ -48,6 +110,18 @@ impl Config {
         }
     }
 
+    fn read() -> Res<Self> {
+        Self::read_from(&Self::get_path())
+    }
+
+    /// The config at `path`, without making it the current one.
+    pub fn read_from(path: &Path) -> Res<Self> {
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read `{}`", path.display()))?;
+
+        toml::from_str(&content).wrap_err_with(|| format!("Unable to parse `{}`", path.display()))
+    }
+
     pub fn save(&self) {
         let path = Self::get_path();
         let content = toml::to_string_pretty(self).expect("Unable to parse Config object");
@@ -72,6 +146,9 @@ pub struct Credentials {
     saucenao_api_key: String,
     #[default = "INVALID"]
     fuzzysearch_api_key: String,
+    /// Users allowed to run `/admin`, on top of the application's owner or team.
+    #[serde(default)]
+    owners: Vec<Id<UserMarker>>,
 }
 
 impl Credentials {
@@ -86,16 +163,425 @@ impl Credentials {
     pub const fn fuzzysearch_api_key(&self) -> &String {
         &self.fuzzysearch_api_key
     }
+
+    pub const fn owners(&self) -> &[Id<UserMarker>] {
+        self.owners.as_slice()
+    }
 }
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
 pub struct Settings {
     #[default = 5]
     top_links: u8,
+    #[serde(default)]
+    saucenao: BackendSettings,
+    #[serde(default)]
+    fuzzysearch: BackendSettings,
+    #[serde(default)]
+    iqdb: BackendSettings,
+    /// Backends to try in order when the chosen one is rate limited or
+    /// fails. Empty by default, so searches stick to the one asked for.
+    #[serde(default)]
+    fallback: Vec<Backend>,
+    #[serde(default)]
+    health_check: HealthCheckSettings,
+    #[serde(default)]
+    history: HistorySettings,
+    /// Remember images posted in channels, to suggest them when someone types
+    /// a link. Needs the message content intent, and only applies on startup.
+    #[serde(default)]
+    cache_messages: bool,
+    #[serde(default)]
+    connection: ConnectionSettings,
+    #[serde(default)]
+    presence: PresenceSettings,
+    #[serde(default)]
+    queue: QueueSettings,
 }
 
 impl Settings {
     pub const fn top_links(&self) -> u8 {
         self.top_links
     }
+
+    pub const fn backend(&self, backend: Backend) -> &BackendSettings {
+        match backend {
+            Backend::SauceNao => &self.saucenao,
+            Backend::FuzzySearch => &self.fuzzysearch,
+            Backend::Iqdb => &self.iqdb,
+        }
+    }
+
+    /// The fallbacks for a search on `backend`, in order, without `backend`
+    /// itself.
+    pub fn fallbacks(&self, backend: Backend) -> Vec<Backend> {
+        let mut fallbacks: Vec<Backend> = Vec::with_capacity(self.fallback.len());
+
+        for &fallback in &self.fallback {
+            if fallback != backend && !fallbacks.contains(&fallback) {
+                fallbacks.push(fallback);
+            }
+        }
+
+        fallbacks
+    }
+
+    pub const fn health_check(&self) -> &HealthCheckSettings {
+        &self.health_check
+    }
+
+    pub const fn history(&self) -> &HistorySettings {
+        &self.history
+    }
+
+    pub const fn cache_messages(&self) -> bool {
+        self.cache_messages
+    }
+
+    pub const fn connection(&self) -> &ConnectionSettings {
+        &self.connection
+    }
+
+    pub const fn presence(&self) -> &PresenceSettings {
+        &self.presence
+    }
+
+    pub const fn queue(&self) -> &QueueSettings {
+        &self.queue
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    /// How long a single attempt may take, in seconds.
+    #[default = 30]
+    timeout_secs: u64,
+    /// How many times a transient failure is retried.
+    #[default = 2]
+    retries: u8,
+    /// Base delay between retries, in milliseconds. Doubles every attempt, up
+    /// to 30 seconds. Retries stop once they'd take more than 4 minutes in all.
+    #[default = 500]
+    backoff_ms: u64,
+    /// Consecutive failed searches before the backend is disabled.
+    #[default = 5]
+    breaker_threshold: u32,
+    /// How long the backend stays disabled, in seconds.
+    #[default = 300]
+    breaker_cooldown_secs: u64,
+}
+
+impl BackendSettings {
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub const fn retries(&self) -> u8 {
+        self.retries
+    }
+
+    pub const fn backoff(&self) -> Duration {
+        Duration::from_millis(self.backoff_ms)
+    }
+
+    pub const fn breaker_threshold(&self) -> u32 {
+        self.breaker_threshold
+    }
+
+    pub const fn breaker_cooldown(&self) -> Duration {
+        Duration::from_secs(self.breaker_cooldown_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckSettings {
+    /// How often backends are probed, in seconds. At least a minute.
+    #[default = 1800]
+    interval_secs: u64,
+    /// How soon a failed probe is retried, in seconds. At least 10 seconds.
+    #[default = 60]
+    retry_secs: u64,
+    /// Consecutive failed probes before a backend is marked as down.
+    #[default = 3]
+    failures_before_down: u32,
+    /// An image every backend should be able to search for.
+    #[default = "https://upload.wikimedia.org/wikipedia/commons/a/a9/Example.jpg"]
+    probe_image: String,
+    /// Which backends to probe. SauceNao is left out by default, as every
+    /// probe eats into its daily quota.
+    #[default(vec![Backend::FuzzySearch, Backend::Iqdb])]
+    backends: Vec<Backend>,
+}
+
+impl HealthCheckSettings {
+    const MIN_INTERVAL_SECS: u64 = 60;
+    const MIN_RETRY_SECS: u64 = 10;
+
+    pub const fn interval(&self) -> Duration {
+        Duration::from_secs(if self.interval_secs < Self::MIN_INTERVAL_SECS {
+            Self::MIN_INTERVAL_SECS
+        } else {
+            self.interval_secs
+        })
+    }
+
+    /// Never longer than [`interval`](Self::interval).
+    pub const fn retry(&self) -> Duration {
+        let interval = self.interval().as_secs();
+        let retry = if self.retry_secs < Self::MIN_RETRY_SECS {
+            Self::MIN_RETRY_SECS
+        } else {
+            self.retry_secs
+        };
+
+        Duration::from_secs(if retry < interval { retry } else { interval })
+    }
+
+    pub const fn failures_before_down(&self) -> u32 {
+        if self.failures_before_down == 0 {
+            1
+        } else {
+            self.failures_before_down
+        }
+    }
+
+    pub const fn probe_image(&self) -> &str {
+        self.probe_image.as_str()
+    }
+
+    pub const fn backends(&self) -> &[Backend] {
+        self.backends.as_slice()
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    /// How long searches stay in someone's `/history`, in days.
+    #[default = 30]
+    retention_days: u64,
+    /// How many searches are kept per user.
+    #[default = 100]
+    max_entries: usize,
+}
+
+impl HistorySettings {
+    pub const fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_days.saturating_mul(24 * 60 * 60))
+    }
+
+    pub const fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+}
+
+/// Lets SauceNao searches wait for the 30 second window instead of being
+/// turned away. Doesn't apply to the 24 hour one.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueSettings {
+    /// How many searches can wait at once. 0 turns the queue off.
+    max_length: usize,
+    /// How long a search waits before giving up, in seconds. Capped at 12
+    /// minutes, so it's answered before Discord's 15 minute interaction
+    /// token runs out.
+    #[default = 300]
+    max_wait_secs: u64,
+}
+
+impl QueueSettings {
+    pub const fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_secs(self.max_wait_secs.min(12 * 60))
+    }
+}
+
+/// How Discord sends the bot interactions. Only applies on startup.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionSettings {
+    mode: ConnectionMode,
+    /// The address to serve the interactions endpoint on in `http` mode.
+    #[default = "0.0.0.0:8080"]
+    listen: String,
+    /// The application's public key from the developer portal, in hex.
+    /// Interactions are checked against it in `http` mode.
+    public_key: String,
+    /// How often to check whether Discord recommends a different number of
+    /// shards, in seconds. 0 turns it off.
+    #[default = 28_800]
+    reshard_interval_secs: u64,
+}
+
+impl ConnectionSettings {
+    pub const fn mode(&self) -> ConnectionMode {
+        self.mode
+    }
+
+    pub const fn listen(&self) -> &str {
+        self.listen.as_str()
+    }
+
+    pub const fn public_key(&self) -> &str {
+        self.public_key.as_str()
+    }
+
+    pub const fn reshard_interval(&self) -> Option<Duration> {
+        match self.reshard_interval_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionMode {
+    /// Connect to the gateway with as many shards as Discord recommends.
+    #[default]
+    Gateway,
+    /// Serve the interactions endpoint instead, e.g. behind a reverse proxy.
+    /// Without the gateway, `cache_messages` has no effect.
+    Http,
+}
+
+/// What every shard shows as the bot's status.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresenceSettings {
+    status: PresenceStatus,
+    /// How long each message is shown before the next, in seconds. Discord
+    /// doesn't allow changing it more often than every 15 seconds.
+    #[default = 300]
+    rotate_secs: u64,
+    /// Shown in turn. `{guilds}`, `{searches_today}` and
+    /// `{saucenao_remaining}` are filled in. Leaving it empty leaves the
+    /// presence to Discord, status included.
+    #[default(vec![PresenceMessage {
+        activity: ActivityKind::Playing,
+        text: "/help - slash commands!".to_owned(),
+    }])]
+    messages: Vec<PresenceMessage>,
+}
+
+impl PresenceSettings {
+    pub const fn status(&self) -> PresenceStatus {
+        self.status
+    }
+
+    pub fn rotate_interval(&self) -> Duration {
+        Duration::from_secs(self.rotate_secs.max(15))
+    }
+
+    pub const fn messages(&self) -> &[PresenceMessage] {
+        self.messages.as_slice()
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PresenceMessage {
+    #[serde(default)]
+    pub activity: ActivityKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceStatus {
+    #[default]
+    Online,
+    Idle,
+    Dnd,
+    Invisible,
+}
+
+impl From<PresenceStatus> for Status {
+    fn from(status: PresenceStatus) -> Self {
+        match status {
+            PresenceStatus::Online => Self::Online,
+            PresenceStatus::Idle => Self::Idle,
+            PresenceStatus::Dnd => Self::DoNotDisturb,
+            PresenceStatus::Invisible => Self::Invisible,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    #[default]
+    Playing,
+    Listening,
+    Watching,
+    Competing,
+}
+
+impl From<ActivityKind> for ActivityType {
+    fn from(kind: ActivityKind) -> Self {
+        match kind {
+            ActivityKind::Playing => Self::Playing,
+            ActivityKind::Listening => Self::Listening,
+            ActivityKind::Watching => Self::Watching,
+            ActivityKind::Competing => Self::Competing,
+        }
+    }
+}
+
+/// What makes an instance of the bot its own, for those running one.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Branding {
+    /// Side colour of every embed, e.g. `0x8BD8C6`.
+    #[default = 0x8B_D8C6]
+    embed_color: u32,
+    /// Where `/issue` sends people.
+    #[default = "https://github.com/lyssieth/sauce-bot/issues"]
+    issue_tracker: String,
+    /// Shown by `/support` under the list of links.
+    #[default = "Any money gained through this will first go towards the VPS and SauceNao rate limits, after which it will go into my pocket."]
+    support_message: String,
+    /// Listed by `/support`, in order.
+    #[default(vec![
+        SupportLink {
+            name: "Patreon".to_owned(),
+            description: "Monthly only.".to_owned(),
+            url: "https://patreon.com/lyssieth".to_owned(),
+        },
+        SupportLink {
+            name: "Github Sponsor".to_owned(),
+            description: "Both one-time and monthly.".to_owned(),
+            url: "https://github.com/sponsors/lyssieth".to_owned(),
+        },
+    ])]
+    support_links: Vec<SupportLink>,
+}
+
+impl Branding {
+    pub const fn embed_color(&self) -> u32 {
+        self.embed_color
+    }
+
+    pub const fn issue_tracker(&self) -> &str {
+        self.issue_tracker.as_str()
+    }
+
+    pub const fn support_message(&self) -> &str {
+        self.support_message.as_str()
+    }
+
+    pub const fn support_links(&self) -> &[SupportLink] {
+        self.support_links.as_slice()
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SupportLink {
+    pub name: String,
+    pub description: String,
+    pub url: String,
 }
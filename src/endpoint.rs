@@ -0,0 +1,221 @@
+//! Serves Discord's interactions endpoint, for running without a gateway
+//! connection.
+//!
+//! Discord POSTs every interaction here, signed with the application's
+//! Ed25519 key. Pings are answered in the response; everything else is handed
+//! to [`events::interaction_create`], which responds through the REST API the
+//! same way it does for interactions from a shard.
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{WrapErr, eyre};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{
+    Method, Request, Response, StatusCode,
+    body::{Bytes, Incoming},
+    header::{CONTENT_TYPE, HeaderValue},
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use sparkle_convenience::Bot;
+use tokio::net::TcpListener;
+use tracing::{debug, error, info, warn};
+use twilight_model::{
+    application::interaction::{Interaction, InteractionType},
+    gateway::payload::incoming::InteractionCreate,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+
+use crate::{Res, config::ConnectionSettings, events};
+
+/// Interactions are a few kilobytes at most, so anything much bigger isn't
+/// from Discord.
+const MAX_BODY: usize = 1024 * 1024;
+
+/// How far `X-Signature-Timestamp` may be from now, so a signed interaction
+/// that was captured can't be replayed later on.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Listens on the configured address until the process exits.
+pub async fn serve(bot: Arc<Bot>, settings: &ConnectionSettings) -> Res<()> {
+    let key = hex::decode(settings.public_key())
+        .wrap_err("`public_key` under `[settings.connection]` isn't valid hex")?;
+
+    if key.len() != 32 {
+        return Err(eyre!(
+            "`public_key` under `[settings.connection]` should be 32 bytes, not {}",
+            key.len()
+        ));
+    }
+
+    let listener = TcpListener::bind(settings.listen())
+        .await
+        .wrap_err_with(|| format!("Unable to listen on {}", settings.listen()))?;
+
+    info!("Serving interactions on {}", listener.local_addr()?);
+
+    run(bot, listener, key).await;
+
+    Ok(())
+}
+
+/// Answers interactions arriving on `listener`, signed with `key`, forever.
+pub async fn run(bot: Arc<Bot>, listener: TcpListener, key: Vec<u8>) {
+    let key = Arc::new(UnparsedPublicKey::new(&ED25519, key));
+
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(?e, "Unable to accept a connection");
+
+                continue;
+            }
+        };
+
+        let bot = bot.clone();
+        let key = key.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let bot = bot.clone();
+                let key = key.clone();
+
+                async move { Ok::<_, Infallible>(handle(bot, &key, request).await) }
+            });
+
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(?e, "Connection from {addr} ended with an error");
+            }
+        });
+    }
+}
+
+async fn handle(
+    bot: Arc<Bot>,
+    key: &UnparsedPublicKey<Vec<u8>>,
+    request: Request<Incoming>,
+) -> Response<Full<Bytes>> {
+    if request.method() != Method::POST {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+
+    let (Some(signature), Some(timestamp)) = (
+        header("x-signature-ed25519"),
+        header("x-signature-timestamp"),
+    ) else {
+        return status(StatusCode::UNAUTHORIZED);
+    };
+
+    if !fresh(&timestamp, SystemTime::now()) {
+        debug!("Refused an interaction signed at {timestamp}");
+
+        return status(StatusCode::UNAUTHORIZED);
+    }
+
+    let body = match Limited::new(request.into_body(), MAX_BODY).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            debug!(?e, "Unable to read an interaction");
+
+            return status(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+    };
+
+    if !verify(key, &timestamp, &body, &signature) {
+        return status(StatusCode::UNAUTHORIZED);
+    }
+
+    let interaction: Interaction = match serde_json::from_slice(&body) {
+        Ok(interaction) => interaction,
+        Err(e) => {
+            warn!(?e, "Unable to parse an interaction");
+
+            return status(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    if interaction.kind == InteractionType::Ping {
+        return pong();
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) =
+            events::interaction_create(bot, Box::new(InteractionCreate(interaction))).await
+        {
+            error!("Error handling interaction: {e}");
+        }
+    });
+
+    status(StatusCode::ACCEPTED)
+}
+
+/// Whether `signature`, in hex, is `key`'s for `timestamp` followed by `body`.
+fn verify(key: &UnparsedPublicKey<Vec<u8>>, timestamp: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    let mut message = Vec::with_capacity(timestamp.len() + body.len());
+    message.extend_from_slice(timestamp.as_bytes());
+    message.extend_from_slice(body);
+
+    key.verify(&message, &signature).is_ok()
+}
+
+/// Whether `timestamp`, in seconds since the epoch, is within
+/// [`MAX_CLOCK_SKEW`] of `now`, either way.
+pub fn fresh(timestamp: &str, now: SystemTime) -> bool {
+    let (Ok(timestamp), Ok(now)) = (timestamp.parse::<u64>(), now.duration_since(UNIX_EPOCH))
+    else {
+        return false;
+    };
+
+    now.as_secs().abs_diff(timestamp) <= MAX_CLOCK_SKEW.as_secs()
+}
+
+fn pong() -> Response<Full<Bytes>> {
+    let response = InteractionResponse {
+        kind: InteractionResponseType::Pong,
+        data: None,
+    };
+
+    match serde_json::to_vec(&response) {
+        Ok(body) => {
+            let mut res = Response::new(Full::new(Bytes::from(body)));
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            res
+        }
+        Err(e) => {
+            error!(?e, "Unable to serialize a pong");
+
+            status(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::default());
+    *res.status_mut() = status;
+
+    res
+}
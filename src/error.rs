@@ -0,0 +1,249 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    hash::{BuildHasher, RandomState},
+    sync::{LazyLock, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use color_eyre::Report;
+use reqwest::StatusCode;
+use twilight_model::id::{Id, marker::InteractionMarker};
+
+use crate::{backend::SearchError, i18n};
+
+/// An error with a message that can be shown to the user as-is.
+#[derive(Debug, Clone)]
+pub struct Friendly(String);
+
+impl Friendly {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl Display for Friendly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Friendly {}
+
+/// Why a backend call failed, as far as the user is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendFailure {
+    BadKey,
+    Unsupported,
+    RateLimited,
+    Timeout,
+    Down,
+    Unknown,
+}
+
+impl BackendFailure {
+    /// Goes by the HTTP error behind `error` where there is one. `sauce_api`
+    /// wraps most other failures as strings, so otherwise the message is all
+    /// there is to go on, without any links in it, as those are the user's
+    /// and can say anything.
+    pub fn classify(error: &(dyn std::error::Error + Send + Sync + 'static)) -> Self {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+
+        while let Some(error) = source {
+            if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+                return Self::from_reqwest(error);
+            }
+
+            source = error.source();
+        }
+
+        let message = without_links(&error.to_string()).to_lowercase();
+
+        if let Some(status) = reqwest_status(&message) {
+            return Self::from_status(status);
+        }
+
+        let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+
+        if has(&["timed out", "timeout", "deadline"]) {
+            Self::Timeout
+        } else if has(&["api key", "api_key", "unauthorized", "forbidden"]) {
+            Self::BadKey
+        } else if has(&["rate limit", "too many requests"]) {
+            Self::RateLimited
+        } else if has(&[
+            "content type",
+            "content-type",
+            "not an image",
+            "unsupported",
+            "invalid image",
+            "file type",
+            "too large",
+        ]) {
+            Self::Unsupported
+        } else if has(&[
+            "connect",
+            "dns",
+            "unavailable",
+            "bad gateway",
+            "html",
+            "parse",
+        ]) {
+            Self::Down
+        } else {
+            Self::Unknown
+        }
+    }
+
+    fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else if let Some(status) = error.status() {
+            Self::from_status(status)
+        } else if error.is_connect() || error.is_decode() || error.is_body() {
+            Self::Down
+        } else {
+            Self::Unknown
+        }
+    }
+
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::BadKey,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::PAYLOAD_TOO_LARGE | StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::Unsupported,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Self::Timeout,
+            status if status.is_server_error() => Self::Down,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// What to tell the user about this failure, in `locale`.
+    pub fn explanation(self, locale: &str) -> String {
+        let id = match self {
+            Self::BadKey => "failure-bad-key",
+            Self::Unsupported => "failure-unsupported",
+            Self::RateLimited => "failure-rate-limited",
+            Self::Timeout => "failure-timeout",
+            Self::Down => "failure-down",
+            Self::Unknown => "failure-unknown",
+        };
+
+        i18n::text(locale, id, &[])
+    }
+}
+
+/// `message` with anything that looks like a link taken out.
+fn without_links(message: &str) -> String {
+    message
+        .split_whitespace()
+        .filter(|word| !word.contains("://"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The status in reqwest's "HTTP status client error (401 Unauthorized)"
+/// message, for when `sauce_api` only kept the message.
+fn reqwest_status(message: &str) -> Option<StatusCode> {
+    let (_, rest) = message.split_once("http status ")?;
+    let (_, rest) = rest.split_once(" error (")?;
+
+    rest.get(..3)?
+        .parse::<u16>()
+        .ok()
+        .and_then(|code| StatusCode::from_u16(code).ok())
+}
+
+/// A short identifier for a failed interaction, shown to the user and
+/// attached to the matching log line.
+pub fn correlation_id(interaction_id: Id<InteractionMarker>) -> String {
+    let hash = RandomState::new().hash_one(interaction_id.get());
+
+    format!("{:08x}", hash & 0xFFFF_FFFF)
+}
+
+/// Builds the message shown to the user for a failed command, in `locale`.
+pub fn explain(error: &Report, locale: &str) -> String {
+    if let Some(friendly) = error.downcast_ref::<Friendly>() {
+        return friendly.to_string();
+    }
+
+    if let Some(error) = error.downcast_ref::<SearchError>() {
+        let backend = error.backend().to_string();
+        let failed = |failure: BackendFailure| {
+            i18n::text(
+                locale,
+                "error-backend",
+                &[
+                    ("backend", backend.clone().into()),
+                    ("explanation", failure.explanation(locale).into()),
+                ],
+            )
+        };
+
+        return match error {
+            SearchError::Timeout(..) => failed(BackendFailure::Timeout),
+            SearchError::Unavailable(_, remaining) => i18n::text(
+                locale,
+                "error-backend-unavailable",
+                &[
+                    ("backend", backend.clone().into()),
+                    ("minutes", remaining.as_secs().div_ceil(60).into()),
+                ],
+            ),
+            SearchError::Disabled(_) => i18n::text(
+                locale,
+                "error-backend-disabled",
+                &[("backend", backend.clone().into())],
+            ),
+            SearchError::Down(_) => i18n::text(
+                locale,
+                "error-backend-down",
+                &[("backend", backend.clone().into())],
+            ),
+            SearchError::Backend(_, e) => failed(BackendFailure::classify(e)),
+        };
+    }
+
+    i18n::text(locale, "error-unknown", &[])
+}
+
+const RECENT_ERRORS: usize = 20;
+
+static RECENT: LazyLock<Mutex<VecDeque<RecentError>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(RECENT_ERRORS)));
+
+/// A failed command, kept around for `/admin errors`.
+#[derive(Debug, Clone)]
+pub struct RecentError {
+    pub at: SystemTime,
+    pub correlation_id: String,
+    pub command: String,
+    pub message: String,
+}
+
+pub fn record(correlation_id: &str, command: &str, error: &Report) {
+    let mut recent = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if recent.len() == RECENT_ERRORS {
+        recent.pop_front();
+    }
+
+    recent.push_back(RecentError {
+        at: SystemTime::now(),
+        correlation_id: correlation_id.to_owned(),
+        command: command.to_owned(),
+        message: error.to_string(),
+    });
+}
+
+/// The most recent errors, newest first.
+pub fn recent() -> Vec<RecentError> {
+    RECENT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .rev()
+        .cloned()
+        .collect()
+}
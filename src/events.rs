@@ -1,45 +1,274 @@
-use std::{ops::Deref, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    ops::Deref,
+    sync::{Arc, LazyLock, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use sparkle_convenience::Bot;
+use sparkle_convenience::{Bot, error::UserError, interaction::DeferVisibility};
 use tracing::{debug, error, info};
-use twilight_interactions::command::{CommandInputData, CommandModel};
+use twilight_interactions::command::{
+    ApplicationCommandData, CommandInputData, CommandModel, CreateCommand,
+};
 use twilight_model::{
     application::{
-        command::CommandType,
-        interaction::{Interaction, InteractionData, InteractionType},
+        command::{CommandOptionChoice, CommandOptionChoiceValue, CommandType},
+        interaction::{
+            Interaction, InteractionContextType, InteractionData, InteractionType,
+            application_command::CommandOptionValue,
+        },
     },
     gateway::payload::incoming::InteractionCreate,
     guild::Permissions,
     id::{
         Id,
-        marker::{CommandMarker, InteractionMarker},
+        marker::{CommandMarker, InteractionMarker, UserMarker},
     },
+    oauth::ApplicationIntegrationType,
 };
 
 use crate::{
     Res,
-    commands::{
-        basic::{HelpCommand, InviteCommand, IssueCommand, SupportCommand},
-        fuzzysearch::FuzzySearch,
-        iqdb::Iqdb,
-        saucenao::Saucenao,
-    },
+    backend::Backend,
+    config::Config,
+    error::{self, Friendly},
+    handle::{Handle, SpecialHandler},
+    i18n, recent,
 };
 
+type Parser = fn(CommandInputData<'_>) -> Res<Box<dyn Cmd + Send + Sync>>;
+
+/// Where a command can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Availability {
+    #[default]
+    Anywhere,
+    GuildOnly,
+    DmOnly,
+    /// Only in servers the bot was added to, not ones where a user installed
+    /// it for themselves.
+    InstalledGuildOnly,
+}
+
+impl Availability {
+    /// Where Discord offers the command. DMs include those with other users
+    /// and group DMs, where the bot can only be through a user install.
+    pub const fn contexts(self) -> &'static [InteractionContextType] {
+        match self {
+            Self::Anywhere => &[
+                InteractionContextType::Guild,
+                InteractionContextType::BotDm,
+                InteractionContextType::PrivateChannel,
+            ],
+            Self::GuildOnly | Self::InstalledGuildOnly => &[InteractionContextType::Guild],
+            Self::DmOnly => &[
+                InteractionContextType::BotDm,
+                InteractionContextType::PrivateChannel,
+            ],
+        }
+    }
+
+    /// How the bot has to be installed for Discord to offer the command.
+    pub const fn integration_types(self) -> &'static [ApplicationIntegrationType] {
+        match self {
+            Self::InstalledGuildOnly => &[ApplicationIntegrationType::GuildInstall],
+            _ => &[
+                ApplicationIntegrationType::GuildInstall,
+                ApplicationIntegrationType::UserInstall,
+            ],
+        }
+    }
+}
+
+/// How a command wants to be run, declared through [`Cmd::metadata`] and
+/// enforced before [`Cmd::execute`] is called.
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    /// Whether to defer the response once [`Cmd::check`] passes.
+    pub defer: bool,
+    /// Whether the response is hidden unless [`Cmd::ephemeral`] says otherwise.
+    pub ephemeral: bool,
+    /// What the bot needs to be allowed to do in the channel.
+    pub permissions: Permissions,
+    /// How long a user has to wait between uses.
+    pub cooldown: Option<Duration>,
+    pub availability: Availability,
+    /// The backend the command always searches with, if any.
+    pub backend: Option<Backend>,
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metadata {
+    pub const fn new() -> Self {
+        Self {
+            defer: false,
+            ephemeral: false,
+            permissions: Permissions::empty(),
+            cooldown: None,
+            availability: Availability::Anywhere,
+            backend: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn defer(mut self) -> Self {
+        self.defer = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn ephemeral(mut self) -> Self {
+        self.ephemeral = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    #[must_use]
+    pub const fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    #[must_use]
+    pub const fn availability(mut self, availability: Availability) -> Self {
+        self.availability = availability;
+        self
+    }
+
+    #[must_use]
+    pub const fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+}
+
+/// A command as the registry knows it.
+#[derive(Clone, Copy)]
+pub struct Entry {
+    create: fn() -> ApplicationCommandData,
+    parse: Parser,
+    metadata: Metadata,
+}
+
+impl Entry {
+    pub fn new<C>() -> Self
+    where
+        C: CreateCommand + CommandModel + Cmd + Send + Sync + 'static,
+    {
+        Self {
+            create: C::create_command,
+            parse: parse::<C>,
+            metadata: C::metadata(),
+        }
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<BTreeMap<String, Entry>>> = LazyLock::new(|| {
+    RwLock::new(
+        crate::commands::builtin()
+            .into_iter()
+            .map(|entry| ((entry.create)().name, entry))
+            .collect(),
+    )
+});
+
+static COOLDOWNS: LazyLock<Mutex<HashMap<(String, Id<UserMarker>), Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Adds a command on top of the built-in ones, replacing any with the same
+/// name. It's created with Discord the next time [`ready`] runs.
+pub fn register<C>()
+where
+    C: CreateCommand + CommandModel + Cmd + Send + Sync + 'static,
+{
+    let entry = Entry::new::<C>();
+
+    REGISTRY
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert((entry.create)().name, entry);
+}
+
+fn parse<C>(data: CommandInputData<'_>) -> Res<Box<dyn Cmd + Send + Sync>>
+where
+    C: CommandModel + Cmd + Send + Sync + 'static,
+{
+    Ok(Box::new(C::from_interaction(data)?))
+}
+
+fn entry(name: &str) -> Option<Entry> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .copied()
+}
+
+/// Every registered command, with what it declared about itself.
+pub fn commands() -> Vec<(ApplicationCommandData, Metadata)> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .map(|entry| ((entry.create)(), entry.metadata))
+        .collect()
+}
+
+/// Everything the bot needs to be allowed to do in a guild: what the commands
+/// declare, plus seeing messages if it remembers posted images.
+pub fn required_permissions() -> Permissions {
+    let commands = commands()
+        .into_iter()
+        .fold(Permissions::empty(), |permissions, (_, metadata)| {
+            permissions | metadata.permissions
+        });
+
+    if Config::current().settings().cache_messages() {
+        commands | Permissions::VIEW_CHANNEL
+    } else {
+        commands
+    }
+}
+
+/// Registers every command globally. Commands stay registered whatever state
+/// their backend is in: a search on a backend that's down is refused when it's
+/// run, with an explanation, unless a fallback can take it.
 pub async fn ready(bot: Arc<Bot>) -> Res<()> {
-    let commands = crate::commands::get();
+    let commands = commands();
     let interaction_client = bot.http.interaction(bot.application.id);
 
-    for x in commands {
-        let cg = interaction_client
+    for (mut x, metadata) in commands {
+        i18n::localize(&mut x);
+
+        let mut cg = interaction_client
             .create_global_command()
             .chat_input(&x.name, &x.description)
             .command_options(&x.options)
             .default_member_permissions(
                 x.default_member_permissions.unwrap_or(Permissions::empty()),
             )
-            .dm_permission(true);
+            .contexts(metadata.availability.contexts())
+            .integration_types(metadata.availability.integration_types());
+
+        if let Some(localizations) = &x.name_localizations {
+            cg = cg.name_localizations(localizations);
+        }
+
+        if let Some(localizations) = &x.description_localizations {
+            cg = cg.description_localizations(localizations);
+        }
 
         match cg.await {
             Ok(cmd) => {
@@ -61,6 +290,10 @@ pub async fn interaction_create(bot: Arc<Bot>, interaction: Box<InteractionCreat
 
     match interaction.kind {
         InteractionType::ApplicationCommand => {}
+        InteractionType::ApplicationCommandAutocomplete => {
+            return autocomplete(bot, &interaction).await;
+        }
+        InteractionType::MessageComponent => return component(bot, &interaction).await,
         _ => return Ok(()),
     }
 
@@ -88,69 +321,193 @@ pub async fn interaction_create(bot: Arc<Bot>, interaction: Box<InteractionCreat
     let cmd = Command {
         name: name.clone(),
         interaction_id,
+        handle: bot.handle(&interaction),
         interaction,
         id: command_id,
         token,
     };
 
+    let Some(entry) = entry(&name) else {
+        debug!("Unhandled interaction: {}", name);
+
+        return Ok(());
+    };
+
     before(&cmd);
 
-    let res = {
-        let ctx = bot.clone();
-        let cmd = cmd.clone();
+    let res = run(bot, &cmd, entry, input_data).await;
 
-        match name.as_str() {
-            "help" => {
-                let help_command = HelpCommand::from_interaction(input_data)?;
+    after(&cmd, res).await;
 
-                help_command.execute(ctx, cmd).await
-            }
+    Ok(())
+}
 
-            "issue" => {
-                let issue_command = IssueCommand::from_interaction(input_data)?;
+/// Suggests recent links while a `link` option is being typed into.
+async fn autocomplete(bot: Arc<Bot>, interaction: &Interaction) -> Res<()> {
+    let Some(InteractionData::ApplicationCommand(data)) = &interaction.data else {
+        return Ok(());
+    };
 
-                issue_command.execute(ctx, cmd).await
-            }
+    let Some((name, typed)) = data.options.iter().find_map(|option| match &option.value {
+        CommandOptionValue::Focused(typed, _) => Some((option.name.as_str(), typed.as_str())),
+        _ => None,
+    }) else {
+        return Ok(());
+    };
 
-            "support" => {
-                let support_command = SupportCommand::from_interaction(input_data)?;
+    let suggestions = match name {
+        "link" => recent::suggestions(
+            interaction.author_id(),
+            interaction.channel.as_ref().map(|channel| channel.id),
+            typed,
+        ),
+        "command" => crate::commands::help::suggestions(typed),
+        _ => return Ok(()),
+    };
 
-                support_command.execute(ctx, cmd).await
-            }
+    let choices = suggestions
+        .into_iter()
+        .map(|link| CommandOptionChoice {
+            name: link.clone(),
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(link),
+        })
+        .collect();
 
-            "invite" => {
-                let invite_command = InviteCommand::from_interaction(input_data)?;
+    bot.handle(interaction).autocomplete(choices).await?;
 
-                invite_command.execute(ctx, cmd).await
-            }
+    Ok(())
+}
 
-            "iqdb" => {
-                let iqdb_command = Iqdb::from_interaction(input_data)?;
+/// Routes a button press by the prefix of its custom ID.
+async fn component(bot: Arc<Bot>, interaction: &Interaction) -> Res<()> {
+    let Some(InteractionData::MessageComponent(data)) = &interaction.data else {
+        return Ok(());
+    };
 
-                iqdb_command.execute(ctx, cmd).await
-            }
+    let handle = bot.handle(interaction);
+    let user = interaction.author_id();
 
-            "saucenao" => {
-                let saucenao_command = Saucenao::from_interaction(input_data)?;
+    let res = match data.custom_id.split_once(':') {
+        Some(("history", id)) => {
+            crate::commands::history::component(handle.clone(), user, id).await
+        }
+        _ => {
+            debug!("Unhandled component: {}", data.custom_id);
 
-                saucenao_command.execute(ctx, cmd).await
-            }
+            return Ok(());
+        }
+    };
 
-            "fuzzysearch" => {
-                let fuzzysearch_command = FuzzySearch::from_interaction(input_data)?;
+    if let Err(e) = res {
+        let correlation_id = error::correlation_id(interaction.id);
 
-                fuzzysearch_command.execute(ctx, cmd).await
-            }
+        error!(%correlation_id, ?e, "Failed to handle component {}", data.custom_id);
+        error::record(&correlation_id, &data.custom_id, &e);
 
-            _ => {
-                debug!("Unhandled interaction: {}", name);
+        if let Err(report_error) = handle.report_error(&e, &correlation_id).await {
+            error!(%correlation_id, ?report_error, "Failed to report error to the user");
+        }
+    }
 
-                return Ok(());
-            }
+    Ok(())
+}
+
+/// Checks everything the command declared, then runs it.
+async fn run(
+    bot: Arc<Bot>,
+    cmd: &Command,
+    entry: Entry,
+    input_data: CommandInputData<'_>,
+) -> Res<()> {
+    let metadata = entry.metadata;
+    let command = (entry.parse)(input_data)?;
+
+    let locale = cmd.handle.locale();
+
+    match (metadata.availability, cmd.guild_id.is_some()) {
+        (Availability::GuildOnly | Availability::InstalledGuildOnly, false) => {
+            return Err(Friendly::new(i18n::text(locale, "only-in-guilds", &[])).into());
         }
-    };
+        (Availability::DmOnly, true) => {
+            return Err(Friendly::new(i18n::text(locale, "only-in-dms", &[])).into());
+        }
+        (Availability::InstalledGuildOnly, true) if cmd.handle.user_installed() => {
+            return Err(Friendly::new(i18n::text(locale, "only-in-installed-guilds", &[])).into());
+        }
+        _ => {}
+    }
 
-    after(&cmd, res);
+    if let Err(UserError::MissingPermissions(missing)) = cmd
+        .handle
+        .check_permissions::<Infallible>(metadata.permissions)
+    {
+        return Err(missing_permissions(locale, missing.unwrap_or(metadata.permissions)).into());
+    }
+
+    command.check(bot.clone(), cmd).await?;
+
+    if let (Some(cooldown), Some(user)) = (metadata.cooldown, cmd.author_id()) {
+        start_cooldown(locale, &cmd.name, user, cooldown)?;
+    }
+
+    if metadata.defer {
+        let ephemeral = command
+            .ephemeral()
+            .unwrap_or(metadata.ephemeral || cmd.handle.user_installed());
+
+        cmd.handle
+            .defer(if ephemeral {
+                DeferVisibility::Ephemeral
+            } else {
+                DeferVisibility::Visible
+            })
+            .await?;
+    }
+
+    command.execute(bot, cmd.clone()).await
+}
+
+fn missing_permissions(locale: &str, missing: Permissions) -> Friendly {
+    let names = missing
+        .iter_names()
+        .map(|(name, _)| format!("`{}`", name.replace('_', " ").to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Friendly::new(i18n::text(
+        locale,
+        "missing-permissions",
+        &[("permissions", names.into())],
+    ))
+}
+
+/// Starts `user`'s cooldown for `name`, unless the last one is still running.
+fn start_cooldown(
+    locale: &str,
+    name: &str,
+    user: Id<UserMarker>,
+    cooldown: Duration,
+) -> Result<(), Friendly> {
+    let now = Instant::now();
+    let mut cooldowns = COOLDOWNS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    cooldowns.retain(|_, until| *until > now);
+
+    if let Some(until) = cooldowns.get(&(name.to_owned(), user)) {
+        let left = until.duration_since(now).as_secs() + 1;
+
+        return Err(Friendly::new(i18n::text(
+            locale,
+            "cooldown",
+            &[
+                ("command", name.to_owned().into()),
+                ("seconds", left.into()),
+            ],
+        )));
+    }
+
+    cooldowns.insert((name.to_owned(), user), now + cooldown);
 
     Ok(())
 }
@@ -159,11 +516,19 @@ fn before(cmd: &Command) {
     info!("Executing command {}", cmd.name);
 }
 
-fn after(cmd: &Command, res: Res<()>) {
-    if let Err(e) = res {
-        error!(?e, "Failed to execute {}", cmd.name);
-    } else {
+async fn after(cmd: &Command, res: Res<()>) {
+    let Err(e) = res else {
         info!("Successfully executed {}", cmd.name);
+        return;
+    };
+
+    let correlation_id = error::correlation_id(cmd.interaction_id);
+
+    error!(%correlation_id, ?e, "Failed to execute {}", cmd.name);
+    error::record(&correlation_id, &cmd.name, &e);
+
+    if let Err(report_error) = cmd.handle.report_error(&e, &correlation_id).await {
+        error!(%correlation_id, ?report_error, "Failed to report error to the user");
     }
 }
 
@@ -171,6 +536,7 @@ fn after(cmd: &Command, res: Res<()>) {
 pub struct Command {
     #[allow(dead_code)]
     pub id: Id<CommandMarker>,
+    pub handle: Handle,
     pub interaction: Interaction,
     pub interaction_id: Id<InteractionMarker>,
     pub name: String,
@@ -187,5 +553,23 @@ impl Deref for Command {
 
 #[async_trait]
 pub trait Cmd {
+    fn metadata() -> Metadata
+    where
+        Self: Sized,
+    {
+        Metadata::new()
+    }
+
+    /// Overrides [`Metadata::ephemeral`] for this use of the command.
+    fn ephemeral(&self) -> Option<bool> {
+        None
+    }
+
+    /// Runs before the response is deferred, so failing here replies
+    /// straight away.
+    async fn check(&self, _ctx: Arc<Bot>, _command: &Command) -> Res<()> {
+        Ok(())
+    }
+
     async fn execute(&self, ctx: Arc<Bot>, command: Command) -> Res<()>;
 }
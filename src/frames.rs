@@ -0,0 +1,434 @@
+//! Picks a few frames out of animated images, since the backends only look at
+//! the first one, if they accept animations at all. Also where images are
+//! downloaded from users' links, for this and anything else that needs them.
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use image::{
+    AnimationDecoder, Frames, ImageFormat, RgbaImage,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+};
+use sauce_api::source::{Item, Output};
+use tokio::net::lookup_host;
+use url::{Host, Url};
+
+use crate::Res;
+
+/// How many frames are searched for, spread evenly from first to last.
+pub const KEY_FRAMES: usize = 3;
+
+/// Animations longer than this are cut short, rather than decoded in full.
+const MAX_FRAMES: usize = 1000;
+
+/// Downloads are given up on once they get bigger than this.
+const MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// How much of a file is looked through at most to tell whether it's
+/// animated.
+const SNIFF_BYTES: usize = 64 * 1024;
+
+/// How long a download may take altogether.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_REDIRECTS: usize = 5;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+const PRIVATE: &str = "only public addresses are downloaded from";
+
+/// Where downloads come from, so they can be swapped out.
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    /// Starts downloading `link`.
+    async fn open(&self, link: &str) -> Res<Box<dyn Body>>;
+}
+
+/// A download in progress.
+#[async_trait]
+pub trait Body: Send {
+    /// How big the whole thing is, if known up front.
+    fn length(&self) -> Option<u64> {
+        None
+    }
+
+    /// The next chunk, or `None` once it's all been read.
+    async fn chunk(&mut self) -> Res<Option<Vec<u8>>>;
+}
+
+#[async_trait]
+impl Body for reqwest::Response {
+    fn length(&self) -> Option<u64> {
+        self.content_length()
+    }
+
+    async fn chunk(&mut self) -> Res<Option<Vec<u8>>> {
+        Ok(Self::chunk(self).await?.map(|chunk| chunk.to_vec()))
+    }
+}
+
+/// Downloads over the web. The links come from users, so only from public
+/// addresses, redirects included.
+pub struct HttpFetcher;
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn client() -> Res<&'static reqwest::Client> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if let Some(reason) = refused(attempt.url()) {
+                attempt.error(reason)
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()?;
+
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+#[async_trait]
+impl Fetcher for HttpFetcher {
+    async fn open(&self, link: &str) -> Res<Box<dyn Body>> {
+        let url = Url::parse(link)?;
+
+        if let Some(reason) = refused(&url) {
+            return Err(eyre!("Not downloading {link}: {reason}"));
+        }
+
+        let response = client()?.get(url).send().await?.error_for_status()?;
+
+        Ok(Box::new(response))
+    }
+}
+
+/// Why `url` won't be downloaded, if it won't. Host names are checked once
+/// they're resolved, by [`PublicResolver`].
+fn refused(url: &Url) -> Option<&'static str> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Some("only web links are downloaded");
+    }
+
+    match url.host()? {
+        Host::Ipv4(ip) if !is_public(ip.into()) => Some(PRIVATE),
+        Host::Ipv6(ip) if !is_public(ip.into()) => Some(PRIVATE),
+        _ => None,
+    }
+}
+
+/// Resolves host names, leaving out any address that isn't public.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve_public(name))
+    }
+}
+
+async fn resolve_public(name: Name) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let addrs: Vec<SocketAddr> = lookup_host((name.as_str(), 0))
+        .await?
+        .filter(|addr| is_public(addr.ip()))
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(PRIVATE.into());
+    }
+
+    Ok(Box::new(addrs.into_iter()))
+}
+
+/// Whether `ip` is out on the internet, rather than loopback, private,
+/// link-local, shared or otherwise special.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (b & 0xC0) == 64)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(ip.into());
+            }
+
+            let first = ip.segments()[0];
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xFE00) == 0xFC00
+                || (first & 0xFFC0) == 0xFE80)
+        }
+    }
+}
+
+#[cfg(test)]
+static OVERRIDE: std::sync::RwLock<Option<Arc<dyn Fetcher>>> = std::sync::RwLock::new(None);
+
+/// Makes every download go through `fetcher` instead.
+#[cfg(test)]
+pub fn set_fetcher(fetcher: Arc<dyn Fetcher>) {
+    *OVERRIDE
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(fetcher);
+}
+
+async fn open(link: &str) -> Res<Box<dyn Body>> {
+    #[cfg(test)]
+    {
+        let fetcher = OVERRIDE
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+
+        if let Some(fetcher) = fetcher {
+            return fetcher.open(link).await;
+        }
+    }
+
+    HttpFetcher.open(link).await
+}
+
+/// Reads the rest of `body` onto `bytes`, unless it's too big to bother with.
+async fn finish(body: &mut dyn Body, mut bytes: Vec<u8>) -> Res<Option<Vec<u8>>> {
+    if body
+        .length()
+        .is_some_and(|length| length > MAX_BYTES as u64)
+    {
+        return Ok(None);
+    }
+
+    while let Some(chunk) = body.chunk().await? {
+        if bytes.len() + chunk.len() > MAX_BYTES {
+            return Ok(None);
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(Some(bytes))
+}
+
+/// Downloads `link`, unless it's too big to bother with.
+pub async fn download(link: &str) -> Res<Option<Vec<u8>>> {
+    let mut body = open(link).await?;
+
+    finish(body.as_mut(), Vec::new()).await
+}
+
+/// Whether the file starting with `head` is animated, or `None` if that
+/// takes more of it to tell. GIFs always count, as their header doesn't say.
+fn sniff(head: &[u8]) -> Option<bool> {
+    if head.len() < 21 {
+        return None;
+    }
+
+    if head.starts_with(b"GIF8") {
+        return Some(true);
+    }
+
+    if head.starts_with(b"RIFF") && &head[8..16] == b"WEBPVP8X" {
+        return Some(head[20] & 0x02 != 0);
+    }
+
+    if !head.starts_with(PNG_SIGNATURE) {
+        return Some(false);
+    }
+
+    // An APNG's acTL chunk has to come before its image data.
+    let mut at = PNG_SIGNATURE.len();
+
+    while let Some(header) = head.get(at..at.saturating_add(8)) {
+        match &header[4..] {
+            b"acTL" => return Some(true),
+            b"IDAT" => return Some(false),
+            _ => {}
+        }
+
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        at = at.saturating_add(12).saturating_add(length as usize);
+    }
+
+    None
+}
+
+/// Downloads `link` and picks its [`KEY_FRAMES`], encoded as PNGs. `None` if
+/// it isn't animated, which is told from the start of it, so only animations
+/// are downloaded in full.
+pub async fn extract(link: &str) -> Res<Option<Vec<Vec<u8>>>> {
+    let mut body = open(link).await?;
+    let mut head = Vec::new();
+
+    let animated = loop {
+        if let Some(animated) = sniff(&head) {
+            break animated;
+        }
+
+        if head.len() >= SNIFF_BYTES {
+            break false;
+        }
+
+        match body.chunk().await? {
+            Some(chunk) => head.extend_from_slice(&chunk),
+            None => break false,
+        }
+    };
+
+    if !animated {
+        return Ok(None);
+    }
+
+    let Some(bytes) = finish(body.as_mut(), head).await? else {
+        return Ok(None);
+    };
+
+    tokio::task::spawn_blocking(move || key_frames(&bytes, KEY_FRAMES)).await?
+}
+
+fn animation(bytes: &[u8]) -> Res<Option<Frames<'_>>> {
+    let cursor = Cursor::new(bytes);
+
+    Ok(match image::guess_format(bytes)? {
+        ImageFormat::Gif => Some(GifDecoder::new(cursor)?.into_frames()),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(cursor)?;
+
+            if decoder.is_apng()? {
+                Some(decoder.apng()?.into_frames())
+            } else {
+                None
+            }
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(cursor)?;
+
+            if decoder.has_animation() {
+                Some(decoder.into_frames())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Picks `wanted` frames out of the animation in `bytes`, encoded as PNGs:
+/// the first, the last, and ones close to evenly spread in between. `None`
+/// if it isn't animated.
+pub fn key_frames(bytes: &[u8], wanted: usize) -> Res<Option<Vec<Vec<u8>>>> {
+    let Some(frames) = animation(bytes)? else {
+        return Ok(None);
+    };
+
+    if wanted == 0 {
+        return Ok(None);
+    }
+
+    // The animation is only decoded once, and how long it is isn't known until
+    // the end of it. So every `stride`th frame is kept along with the latest,
+    // and the stride doubles whenever that's too many to keep in memory.
+    let mut stride = 1;
+    let mut kept: Vec<(usize, RgbaImage)> = Vec::new();
+    let mut latest = None;
+    let mut total = 0;
+
+    for (n, frame) in frames.take(MAX_FRAMES).enumerate() {
+        let frame = match frame {
+            Ok(frame) => frame.into_buffer(),
+            Err(e) if total == 0 => return Err(e.into()),
+            Err(_) => break,
+        };
+        total = n + 1;
+
+        if n % stride != 0 {
+            latest = Some((n, frame));
+
+            continue;
+        }
+
+        kept.push((n, frame));
+        latest = None;
+
+        if kept.len() > 4 * wanted {
+            stride *= 2;
+
+            if let Some(newest) = kept.pop_if(|&mut (n, _)| n % stride != 0) {
+                latest = Some(newest);
+            }
+            kept.retain(|&(n, _)| n % stride == 0);
+        }
+    }
+
+    kept.extend(latest);
+
+    if total < 2 {
+        return Ok(None);
+    }
+
+    // The kept frames closest to ones spread evenly from first to last.
+    let mut picked: Vec<usize> = (0..wanted)
+        .map(|n| n * (total - 1) / wanted.saturating_sub(1).max(1))
+        .filter_map(|target| (0..kept.len()).min_by_key(|&i| kept[i].0.abs_diff(target)))
+        .collect();
+    picked.dedup();
+
+    let mut res = Vec::with_capacity(picked.len());
+
+    for i in picked {
+        let mut png = Cursor::new(Vec::new());
+        kept[i].1.write_to(&mut png, ImageFormat::Png)?;
+
+        res.push(png.into_inner());
+    }
+
+    Ok(Some(res))
+}
+
+/// Combines what each frame turned up, keeping the best similarity for every
+/// link found more than once.
+pub fn merge(original_url: String, outputs: Vec<Output>) -> Output {
+    let mut best: HashMap<String, f32> = HashMap::new();
+
+    for item in outputs.into_iter().flat_map(|output| output.items) {
+        let similarity = best.entry(item.link).or_insert(item.similarity);
+
+        *similarity = similarity.max(item.similarity);
+    }
+
+    Output {
+        original_url,
+        items: best
+            .into_iter()
+            .map(|(link, similarity)| Item { link, similarity })
+            .collect(),
+    }
+}
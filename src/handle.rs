@@ -3,20 +3,22 @@ use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
 };
 
+use color_eyre::Report;
 use sparkle_convenience::{
     Bot,
     error::{Error, UserError},
+    interaction::DeferVisibility,
     reply::Reply,
 };
 use twilight_model::{
     application::{
         command::CommandOptionChoice,
-        interaction::{Interaction, InteractionType},
+        interaction::{Interaction, InteractionContextType, InteractionType},
     },
     channel::{
         Message,
         message::{
-            Component,
+            AllowedMentions, Component, MessageFlags,
             component::{ActionRow, TextInput},
         },
     },
@@ -36,6 +38,9 @@ pub struct Handle {
     token: String,
     kind: InteractionType,
     app_permissions: Permissions,
+    nsfw: bool,
+    locale: String,
+    user_installed: bool,
     responded: Arc<AtomicBool>,
     last_message_id: Arc<AtomicU64>,
 }
@@ -52,14 +57,63 @@ impl SpecialHandler for Arc<Bot> {
             token: interaction.token.clone(),
             kind: interaction.kind,
             app_permissions: interaction.app_permissions.unwrap_or(Permissions::all()),
+            nsfw: interaction
+                .channel
+                .as_ref()
+                .and_then(|channel| channel.nsfw)
+                .unwrap_or_default(),
+            locale: interaction
+                .locale
+                .clone()
+                .or_else(|| interaction.guild_locale.clone())
+                .unwrap_or_else(|| crate::i18n::DEFAULT.to_owned()),
+            user_installed: match interaction.context {
+                Some(InteractionContextType::Guild) => {
+                    interaction.authorizing_integration_owners.guild.is_none()
+                }
+                Some(InteractionContextType::PrivateChannel) => true,
+                _ => false,
+            },
             responded: Arc::new(AtomicBool::new(false)),
             last_message_id: Arc::new(AtomicU64::new(0)),
         }
     }
 }
 
+/// A reply that can't ping anyone, whatever ends up in it.
+pub fn quiet_reply() -> Reply {
+    Reply::new().allowed_mentions(Some(AllowedMentions::default()))
+}
+
 #[allow(unused)]
 impl Handle {
+    /// Whether the interaction came from an age-restricted channel. DMs
+    /// don't count.
+    pub const fn nsfw(&self) -> bool {
+        self.nsfw
+    }
+
+    /// The user's locale, or the guild's if Discord didn't say.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Whether the bot is only here because the user installed it for
+    /// themselves, as in a server it hasn't been added to or a group DM.
+    pub const fn user_installed(&self) -> bool {
+        self.user_installed
+    }
+
+    /// Whether a reply should be hidden: as `requested`, or by default where
+    /// the bot is [user installed](Self::user_installed), so using it there
+    /// doesn't post in front of everyone.
+    pub const fn ephemeral(&self, requested: Option<bool>) -> bool {
+        match requested {
+            Some(ephemeral) => ephemeral,
+            None => self.user_installed,
+        }
+    }
+
     pub const fn check_permissions<C>(
         &self,
         required_permissions: Permissions,
@@ -72,24 +126,55 @@ impl Handle {
         Ok(())
     }
 
-    pub async fn report_error<C: Send>(
+    pub async fn defer(&self, visibility: DeferVisibility) -> Result<(), Error> {
+        if self.responded() {
+            return Err(Error::AlreadyResponded);
+        }
+
+        let interaction = self.bot.http.interaction(self.bot.application.id);
+
+        interaction
+            .create_response(
+                self.id,
+                &self.token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                    data: Some(InteractionResponseData {
+                        flags: matches!(visibility, DeferVisibility::Ephemeral)
+                            .then_some(MessageFlags::EPHEMERAL),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .await?;
+
+        self.set_responded(true);
+
+        Ok(())
+    }
+
+    /// Tells the user a command failed, replacing the "thinking..." message if
+    /// the interaction was deferred.
+    pub async fn report_error(
         &self,
-        reply: Reply,
-        error: UserError<C>,
+        error: &Report,
+        correlation_id: &str,
     ) -> Result<Option<Message>, Error> {
-        if matches!(error, UserError::Ignore) {
-            return Ok(None);
-        }
+        let mut reply = quiet_reply().ephemeral().content(format!(
+            "{}\n-# {}",
+            crate::error::explain(error, self.locale()),
+            crate::i18n::text(
+                self.locale(),
+                "error-id",
+                &[("id", correlation_id.to_owned().into())]
+            )
+        ));
 
-        match self.reply(reply).await {
-            Ok(message) => Ok(message),
-            Err(Error::Http(err))
-                if matches!(UserError::<C>::from_http_err(&err), UserError::Internal) =>
-            {
-                Err(Error::Http(err))
-            }
-            Err(err) => Err(err),
+        if self.last_message_id().is_none() {
+            reply = reply.update_last();
         }
+
+        self.reply(reply).await
     }
 
     pub async fn reply(&self, reply: Reply) -> Result<Option<Message>, Error> {
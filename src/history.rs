@@ -0,0 +1,297 @@
+//! Opt-in search history, kept in `history.toml` next to the config.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write as _},
+    sync::{
+        LazyLock, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sauce_api::source::Output;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use twilight_model::id::{Id, marker::UserMarker};
+
+use crate::{backend::Backend, config::Config};
+
+/// One search, as shown by `/history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    pub backend: Backend,
+    pub link: String,
+    pub results: Vec<Found>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Found {
+    pub link: String,
+    pub similarity: f32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UserHistory {
+    enabled: bool,
+    #[serde(default)]
+    next_id: u64,
+    /// Oldest first.
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+/// Keyed by user ID, as TOML only allows string keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    #[serde(default)]
+    users: BTreeMap<String, UserHistory>,
+}
+
+static STORE: LazyLock<Mutex<Store>> = LazyLock::new(|| Mutex::new(Store::load()));
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Counts saves, so one finishing late doesn't overwrite a newer one.
+static SAVES: AtomicU64 = AtomicU64::new(0);
+
+/// The last save written to disk, held while writing.
+static WRITTEN: Mutex<u64> = Mutex::new(0);
+
+impl Store {
+    /// Whatever was kept in `history.toml`, less what's past the retention
+    /// period.
+    fn load() -> Self {
+        let broken = Config::data_path("history.toml.broken");
+
+        if broken.exists() {
+            warn!(
+                "`{}` holds users' search history, which `/history clear` and the retention period don't reach. Delete it once it's been looked at",
+                broken.display()
+            );
+        }
+
+        let mut store = Self::read();
+        store.prune(&Config::current());
+
+        store
+    }
+
+    /// If `history.toml` is unreadable, starts over, moving a file that
+    /// couldn't be parsed out of the way first.
+    fn read() -> Self {
+        let path = Config::data_path("history.toml");
+
+        if !path.exists() {
+            return Self::default();
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!(
+                    ?e,
+                    "Unable to read `history.toml`, starting with no history"
+                );
+
+                return Self::default();
+            }
+        };
+
+        match toml::from_str(&content) {
+            Ok(store) => store,
+            Err(e) => {
+                error!(
+                    ?e,
+                    "Unable to parse `history.toml`, starting with no history and keeping it as `history.toml.broken`"
+                );
+
+                if let Err(e) = fs::rename(&path, path.with_extension("toml.broken")) {
+                    error!(?e, "Unable to move `history.toml` out of the way");
+                }
+
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the store out in the background, so neither the runtime nor
+    /// anyone waiting on [`STORE`] is held up by the disk.
+    fn save(&self) {
+        let content = match toml::to_string_pretty(self) {
+            Ok(content) => content,
+            Err(e) => {
+                error!(?e, "Unable to serialize the search history");
+
+                return;
+            }
+        };
+        let save = SAVES.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+
+        tokio::task::spawn_blocking(move || {
+            let mut written = WRITTEN.lock().unwrap_or_else(PoisonError::into_inner);
+
+            // A later save got there first.
+            if *written > save {
+                return;
+            }
+
+            match write(&content) {
+                Ok(()) => *written = save,
+                Err(e) => error!(?e, "Unable to write `history.toml`"),
+            }
+        });
+    }
+
+    /// Drops whatever is past the retention period or over the limit, and
+    /// anyone with history off and nothing left. Returns whether anything was.
+    fn prune(&mut self, cfg: &Config) -> bool {
+        let settings = cfg.settings().history();
+        let oldest = now().saturating_sub(settings.retention().as_secs());
+        let mut pruned = false;
+
+        for history in self.users.values_mut() {
+            let before = history.entries.len();
+            history.entries.retain(|entry| entry.at >= oldest);
+
+            let over = history.entries.len().saturating_sub(settings.max_entries());
+            history.entries.drain(..over);
+
+            pruned |= history.entries.len() != before;
+        }
+
+        let before = self.users.len();
+        self.users
+            .retain(|_, history| history.enabled || !history.entries.is_empty());
+
+        pruned || self.users.len() != before
+    }
+}
+
+/// Writes `content` to a temporary file first, so `history.toml` is never left
+/// half-written.
+fn write(content: &str) -> io::Result<()> {
+    let path = Config::data_path("history.toml");
+    let temp = path.with_extension("toml.tmp");
+
+    let mut file = File::create(&temp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(temp, path)
+}
+
+/// Prunes everyone's history every hour, so it's gone once it's past the
+/// retention period even for users who stopped searching.
+pub async fn prune_periodically() {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let cfg = Config::current();
+        let mut store = store();
+
+        if store.prune(&cfg) {
+            store.save();
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+fn store() -> MutexGuard<'static, Store> {
+    STORE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn is_enabled(user: Id<UserMarker>) -> bool {
+    store()
+        .users
+        .get(&user.to_string())
+        .is_some_and(|history| history.enabled)
+}
+
+/// Turns history on or off for `user`, keeping what's already there.
+pub fn set_enabled(user: Id<UserMarker>, enabled: bool) {
+    let mut store = store();
+
+    store.users.entry(user.to_string()).or_default().enabled = enabled;
+    store.save();
+}
+
+/// Forgets everything about `user`, including whether history was on.
+pub fn clear(user: Id<UserMarker>) {
+    let mut store = store();
+
+    if store.users.remove(&user.to_string()).is_some() {
+        store.save();
+    }
+}
+
+/// Adds a successful search to `user`'s history, if they turned it on.
+pub fn record(user: Option<Id<UserMarker>>, backend: Backend, link: &str, output: &Output) {
+    let Some(user) = user else {
+        return;
+    };
+
+    let cfg = Config::current();
+    let mut store = store();
+
+    let Some(history) = store
+        .users
+        .get_mut(&user.to_string())
+        .filter(|history| history.enabled)
+    else {
+        return;
+    };
+
+    let mut results: Vec<Found> = output
+        .items
+        .iter()
+        .map(|item| Found {
+            link: item.link.clone(),
+            similarity: item.similarity,
+        })
+        .collect();
+    results.sort_unstable_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    results.truncate(cfg.settings().top_links() as usize);
+
+    history.next_id += 1;
+    history.entries.push(Entry {
+        id: history.next_id,
+        at: now(),
+        backend,
+        link: link.to_owned(),
+        results,
+    });
+
+    store.prune(&cfg);
+    store.save();
+}
+
+/// `user`'s history, newest first.
+pub fn entries(user: Id<UserMarker>) -> Vec<Entry> {
+    let cfg = Config::current();
+    let mut store = store();
+
+    store.prune(&cfg);
+
+    store
+        .users
+        .get(&user.to_string())
+        .map(|history| history.entries.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn entry(user: Id<UserMarker>, id: u64) -> Option<Entry> {
+    entries(user).into_iter().find(|entry| entry.id == id)
+}
@@ -0,0 +1,180 @@
+//! Translations of what the bot says, picked by the locale of the interaction.
+//!
+//! Each locale is a Fluent file in `src/locales`, named after the Discord
+//! locale it's for. Anything a locale doesn't translate falls back to English.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+use tracing::error;
+use twilight_interactions::command::ApplicationCommandData;
+use twilight_model::application::command::{CommandOption, CommandOptionChoiceValue};
+use unic_langid::LanguageIdentifier;
+
+/// The locale commands are declared in, and everything falls back to.
+pub const DEFAULT: &str = "en-US";
+
+const LOCALES: &[(&str, &str)] = &[
+    ("en-US", include_str!("locales/en-US.ftl")),
+    ("de", include_str!("locales/de.ftl")),
+];
+
+struct Locale {
+    code: &'static str,
+    bundle: FluentBundle<FluentResource>,
+}
+
+static BUNDLES: LazyLock<Vec<Locale>> = LazyLock::new(|| {
+    LOCALES
+        .iter()
+        .map(|&(code, source)| Locale {
+            code,
+            bundle: bundle(code, source),
+        })
+        .collect()
+});
+
+/// Every locale there are translations for, other than [`DEFAULT`].
+#[cfg(test)]
+pub fn translated() -> impl Iterator<Item = &'static str> {
+    LOCALES
+        .iter()
+        .map(|&(code, _)| code)
+        .filter(|&code| code != DEFAULT)
+}
+
+fn bundle(code: &str, source: &str) -> FluentBundle<FluentResource> {
+    let language: LanguageIdentifier = code.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+
+    // Isolation marks around arguments would end up inside links.
+    bundle.set_use_isolating(false);
+
+    let resource =
+        FluentResource::try_new(source.to_owned()).unwrap_or_else(|(resource, errors)| {
+            error!(?errors, "Unable to parse all of the {code} locale");
+
+            resource
+        });
+
+    if let Err(errors) = bundle.add_resource(resource) {
+        error!(?errors, "Unable to add all of the {code} locale");
+    }
+
+    bundle
+}
+
+/// The locale that best matches the Discord locale `requested`, going by its
+/// language alone if there's no exact match.
+fn find(requested: &str) -> Option<&'static Locale> {
+    let language = |code: &str| code.split('-').next().map(str::to_ascii_lowercase);
+
+    BUNDLES
+        .iter()
+        .find(|locale| locale.code.eq_ignore_ascii_case(requested))
+        .or_else(|| {
+            BUNDLES
+                .iter()
+                .find(|locale| language(locale.code) == language(requested))
+        })
+}
+
+/// Message `id` in `locale`, filled in with `args`.
+pub fn text(locale: &str, id: &str, args: &[(&str, FluentValue<'_>)]) -> String {
+    let args: Option<FluentArgs<'_>> = (!args.is_empty()).then(|| args.iter().cloned().collect());
+
+    for locale in [find(locale), find(DEFAULT)].into_iter().flatten() {
+        let Some(pattern) = locale
+            .bundle
+            .get_message(id)
+            .and_then(|message| message.value())
+        else {
+            continue;
+        };
+
+        let mut errors = Vec::new();
+        let text = locale
+            .bundle
+            .format_pattern(pattern, args.as_ref(), &mut errors);
+
+        if !errors.is_empty() {
+            error!(
+                ?errors,
+                "Unable to format {id} in the {} locale", locale.code
+            );
+        }
+
+        return text.into_owned();
+    }
+
+    error!("Missing message {id}");
+
+    id.to_owned()
+}
+
+/// Translations of message `id`, or of its `attribute`, keyed by Discord
+/// locale. English is left out, as that's what commands are declared in.
+fn localizations(id: &str, attribute: Option<&str>) -> Option<HashMap<String, String>> {
+    let res: HashMap<String, String> = BUNDLES
+        .iter()
+        .filter(|locale| locale.code != DEFAULT)
+        .filter_map(|locale| {
+            let message = locale.bundle.get_message(id)?;
+            let pattern = match attribute {
+                Some(attribute) => message.get_attribute(attribute)?.value(),
+                None => message.value()?,
+            };
+
+            let mut errors = Vec::new();
+            let text = locale.bundle.format_pattern(pattern, None, &mut errors);
+
+            Some((locale.code.to_owned(), text.into_owned()))
+        })
+        .collect();
+
+    (!res.is_empty()).then_some(res)
+}
+
+/// The translation of a command's `english` name or description for `locale`,
+/// out of the `localizations` that [`localize`] filled in.
+pub fn pick<'a>(
+    english: &'a str,
+    localizations: Option<&'a HashMap<String, String>>,
+    locale: &str,
+) -> &'a str {
+    find(locale)
+        .and_then(|locale| localizations?.get(locale.code))
+        .map_or(english, String::as_str)
+}
+
+/// Fills in the translated names and descriptions of `command`, its options
+/// and their choices, from `command-<name>[-<option>...]` and
+/// `choice-<option>-<value>`.
+pub fn localize(command: &mut ApplicationCommandData) {
+    let id = format!("command-{}", command.name);
+
+    command.name_localizations = localizations(&id, None);
+    command.description_localizations = localizations(&id, Some("description"));
+
+    localize_options(&id, &mut command.options);
+}
+
+fn localize_options(parent: &str, options: &mut [CommandOption]) {
+    for option in options {
+        let id = format!("{parent}-{}", option.name);
+
+        option.name_localizations = localizations(&id, None);
+        option.description_localizations = localizations(&id, Some("description"));
+
+        for choice in option.choices.iter_mut().flatten() {
+            if let CommandOptionChoiceValue::String(value) = &choice.value {
+                choice.name_localizations =
+                    localizations(&format!("choice-{}-{value}", option.name), None);
+            }
+        }
+
+        if let Some(options) = &mut option.options {
+            localize_options(&id, options);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! A Discord bot which finds the original source of images.
+//!
+//! [`SauceBot`] builds the bot from a [`Config`] and runs its shards, or
+//! serves the interactions endpoint in their place. Extra slash commands can
+//! be added through the [`Cmd`] trait.
+
+#![warn(clippy::pedantic, clippy::nursery, clippy::perf)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::must_use_candidate
+)]
+#![deny(clippy::unwrap_used, clippy::panic)]
+
+use std::sync::Arc;
+
+use sparkle_convenience::Bot;
+use tracing::{info, warn};
+use twilight_gateway::{EventTypeFlags, Intents};
+use twilight_http::Client;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+
+use crate::config::ConnectionMode;
+pub use crate::{
+    backend::Backend,
+    commands::PreprocessOption,
+    config::Config,
+    events::{Availability, Cmd, Command, Metadata},
+    handle::Handle,
+};
+
+mod backend;
+mod commands;
+mod config;
+mod endpoint;
+mod error;
+mod events;
+mod frames;
+mod handle;
+mod history;
+mod i18n;
+mod preprocess;
+mod presence;
+pub mod rate_limiter;
+mod recent;
+pub mod sauce_finder;
+mod shards;
+#[cfg(test)]
+mod tests;
+
+pub type Res<T> = color_eyre::Result<T>;
+
+/// A logged-in bot, ready to be run.
+pub struct SauceBot {
+    bot: Arc<Bot>,
+}
+
+impl SauceBot {
+    /// Makes `cfg` the current config and logs in with its token.
+    pub async fn new(cfg: Config) -> Res<Self> {
+        let token = cfg.credentials().token().clone();
+        let mode = cfg.settings().connection().mode();
+        let mut intents = Intents::empty();
+        let mut event_types = EventTypeFlags::INTERACTION_CREATE
+            | EventTypeFlags::READY
+            | EventTypeFlags::GATEWAY_HEARTBEAT_ACK
+            | EventTypeFlags::GATEWAY_CLOSE;
+
+        if cfg.settings().cache_messages() {
+            if mode == ConnectionMode::Http {
+                warn!("`cache_messages` has no effect without the gateway");
+            }
+
+            intents |=
+                Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES | Intents::MESSAGE_CONTENT;
+            event_types |= EventTypeFlags::MESSAGE_CREATE;
+        }
+
+        Config::set_current(cfg);
+
+        let bot = login(token, intents, event_types).await?;
+
+        Ok(Self { bot: Arc::new(bot) })
+    }
+
+    /// Adds a slash command on top of the built-in ones.
+    #[must_use]
+    pub fn command<C>(self) -> Self
+    where
+        C: CreateCommand + CommandModel + Cmd + Send + Sync + 'static,
+    {
+        events::register::<C>();
+
+        self
+    }
+
+    pub const fn bot(&self) -> &Arc<Bot> {
+        &self.bot
+    }
+
+    /// Registers the commands, then runs the shards or serves the
+    /// interactions endpoint, along with the backend health checks and history
+    /// pruning, until the process exits.
+    pub async fn run(self) -> Res<()> {
+        commands::status::mark_started();
+
+        info!("Starting...");
+
+        tokio::spawn(backend::health_check());
+        tokio::spawn(history::prune_periodically());
+
+        events::ready(self.bot.clone()).await?;
+
+        let cfg = Config::current();
+        match cfg.settings().connection().mode() {
+            ConnectionMode::Gateway => shards::run(self.bot).await,
+            ConnectionMode::Http => endpoint::serve(self.bot, cfg.settings().connection()).await,
+        }
+    }
+}
+
+/// Logs in over HTTP alone, as [`Bot::new`] would also create shards, which
+/// [`shards`] takes care of instead.
+async fn login(token: String, intents: Intents, event_types: EventTypeFlags) -> Res<Bot> {
+    let http = Client::new(token);
+    let application = http.current_user_application().await?.model().await?;
+    let user = http.current_user().await?.model().await?;
+
+    Ok(Bot {
+        http,
+        application,
+        user,
+        intents,
+        event_type_flags: event_types,
+        logging_channel_id: None,
+        logging_webhook: None,
+        logging_file_path: None,
+    })
+}
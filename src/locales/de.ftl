@@ -0,0 +1,299 @@
+# Alles, was der Bot sagt, auf Deutsch.
+#
+# `command-*` übersetzt Befehle: der Wert ist der Name, `.description` die
+# Beschreibung. Namen müssen kleingeschrieben sein und dürfen keine
+# Leerzeichen enthalten. `choice-<option>-<wert>` übersetzt Auswahlmöglichkeiten.
+
+## Suchen
+
+invalid-link = Ungültiger Link
+invalid-attachment = Ungültiger Anhang
+video-attachment = Nach Videos kann nicht gesucht werden. Versuch es mit einem GIF oder einem Screenshot des gesuchten Bildes.
+no-image = Es wurde kein Bild angegeben, weder als Link noch als Anhang.
+
+searching-frames = Das ist eine Animation, daher wird stattdessen nach { $count } ihrer Einzelbilder gesucht.
+searching-tiles = Das sieht nach einer Collage aus, daher wird stattdessen nach jeder ihrer { $count } Kacheln gesucht.
+searching-cropped = Stattdessen wird nach dem Bild ohne Ränder gesucht.
+
+results-title = Ergebnisse
+results-original = Ursprünglicher Link
+results-original-hidden = Nicht angezeigt, da es kein Weblink ist.
+results-none = Keine Ergebnisse gefunden
+results-none-description = Für den angegebenen Link wurden keine Ergebnisse gefunden.
+results-similarity = Ähnlichkeit: { $similarity }
+results-backend = Gesucht mit { $backend }
+
+batch-too-many = Das sind { $count } Bilder, aber es können nur bis zu { $max } auf einmal gesucht werden.
+batch-failed = Bild { $number } (<{ $link }>): { $reason }
+batch-failed-hidden = Bild { $number }: { $reason }
+
+## SauceNao-Limits
+
+saucenao-limited-short = Du wurdest ausgebremst. Bitte warte bis zu 30 Sekunden, bevor du es erneut versuchst. (Sorry, so sind die Limits bei SauceNao. Vielleicht magst du den Ersteller des Bots mit `/support` unterstützen)
+saucenao-limited-long = Du wurdest ausgebremst. Bitte warte bis zu 24 Stunden. (Sorry, so sind die Limits bei SauceNao. Vielleicht magst du den Ersteller des Bots mit `/support` unterstützen)
+saucenao-queued = SauceNao ist gerade ausgelastet, daher wartet deine Suche, bis sie dran ist. Platz in der Warteschlange: { $position }
+saucenao-queue-searching = Du bist dran, es wird jetzt gesucht.
+
+## /help, /issue, /support und /invite
+
+help-title = Hilfe
+help-description = Alle Befehle sind Slash-Befehle. Suchen können ein paar Sekunden dauern, da sie einen möglicherweise langsamen Webdienst aufrufen. Mit `/help <befehl>` gibt es Details zu einem Befehl.
+help-commands = Befehle
+help-backends = Backends
+help-backend-limit = { $available }/{ $max } Suchen pro { $window } übrig, wird in { $resets_in } zurückgesetzt
+help-backend-unlimited = Keine Limits seitens des Bots
+help-settings = Einstellungen
+help-settings-description =
+    - Angezeigte Links pro Suche: bis zu { $top_links }
+    - Der Suchverlauf wird, falls eingeschaltet, { $retention_days } Tage lang aufbewahrt, bis zu { $max_entries } Suchen
+help-options = Optionen
+help-subcommands = Unterbefehle
+help-required = erforderlich
+help-usage = Verwendung
+help-cooldown = Kann einmal alle { $cooldown } verwendet werden.
+help-anywhere = Funktioniert auf Servern und in DMs.
+help-guild-only = Funktioniert nur auf Servern.
+help-dm-only = Funktioniert nur in DMs.
+help-installed-guild-only = Funktioniert nur auf Servern, zu denen der Bot hinzugefügt wurde.
+help-hidden = Antworten sind versteckt.
+help-unknown-command = Es gibt keinen Befehl `/{ $command }`. Mit `/help` gibt es die vollständige Liste.
+
+issue = Um ein Problem zu melden, geh bitte zu <{ $url }>
+
+support-title = Unterstützen
+support-description = Alle Möglichkeiten, SauceBot zu unterstützen.
+support-link = Link
+
+invite = Um den Bot auf deinen Server einzuladen, geh bitte zu <{ $url }>
+
+## Fehler
+
+error-unknown = Beim Ausführen dieses Befehls ist etwas schiefgelaufen.
+error-id = Fehler-ID: `{ $id }`
+error-backend = { $backend }: { $explanation }
+error-backend-unavailable = { $backend } ist vorübergehend deaktiviert, nachdem es wiederholt fehlgeschlagen ist. Es wird in etwa { $minutes } { $minutes ->
+        [one] Minute
+       *[other] Minuten
+    } erneut versucht, bis dahin nutze bitte ein anderes Backend.
+error-backend-disabled = { $backend } wurde vom Betreiber des Bots abgeschaltet. Bitte nutze ein anderes Backend.
+error-backend-down = { $backend } ist gerade nicht erreichbar. Der Bot prüft das regelmäßig und schaltet es von selbst wieder ein, sobald es zurück ist, bis dahin nutze bitte ein anderes Backend.
+
+failure-bad-key = Das Such-Backend hat den API-Schlüssel des Bots abgelehnt. Das ist ein Problem auf unserer Seite, bitte melde es mit `/issue`.
+failure-unsupported = Das Such-Backend konnte das Bild nicht lesen. Achte darauf, dass der Link direkt auf ein nicht zu großes PNG, JPEG, GIF oder WebP zeigt.
+failure-rate-limited = Das Such-Backend bremst den Bot gerade aus. Bitte versuch es in einer Weile erneut.
+failure-timeout = Das Such-Backend hat zu lange für eine Antwort gebraucht. Bitte versuch es in einer Weile erneut.
+failure-down = Das Such-Backend scheint gerade nicht erreichbar zu sein. Bitte versuch es später erneut, oder nutze ein anderes Backend.
+failure-unknown = Das Such-Backend hat einen unerwarteten Fehler zurückgegeben.
+
+only-in-guilds = Dieser Befehl kann nur auf Servern verwendet werden.
+only-in-dms = Dieser Befehl kann nur in DMs verwendet werden.
+only-in-installed-guilds = Dieser Befehl kann nur auf Servern verwendet werden, zu denen der Bot hinzugefügt wurde.
+missing-permissions = Mir fehlen in diesem Kanal Berechtigungen dafür: { $permissions }.
+cooldown = Du verwendest `/{ $command }` zu schnell. Bitte versuch es in { $seconds } { $seconds ->
+        [one] Sekunde
+       *[other] Sekunden
+    } erneut.
+
+## Backends, wie von /status und /help angezeigt
+
+backend-disabled = Vom Betreiber deaktiviert
+backend-open = Nach wiederholten Fehlern noch etwa { $minutes } { $minutes ->
+        [one] Minute
+       *[other] Minuten
+    } deaktiviert
+backend-down = Nicht erreichbar, letzte Prüfung fehlgeschlagen
+backend-down-checked = Nicht erreichbar, Prüfung vor { $minutes } { $minutes ->
+        [one] Minute
+       *[other] Minuten
+    } fehlgeschlagen
+backend-available = Verfügbar
+backend-latency = Verfügbar, hat zuletzt in { $seconds } s geantwortet
+
+## /status
+
+status-title = Status
+status-quota = SauceNao-Kontingent
+status-quota-value =
+    - Kurz: { $short_available }/{ $short_max } übrig, wird in { $short_resets_in } zurückgesetzt
+    - Lang: { $long_available }/{ $long_max } übrig, wird in { $long_resets_in } zurückgesetzt
+status-uptime = Laufzeit
+status-servers = Server
+status-servers-unknown = Unbekannt
+status-cache = Cache
+status-cache-searched = - Letzte Suchen: { $links } { $links ->
+        [one] Link
+       *[other] Links
+    } von { $users } { $users ->
+        [one] Nutzer
+       *[other] Nutzern
+    }
+status-cache-posted = - Gepostete Bilder: { $links } { $links ->
+        [one] Link
+       *[other] Links
+    } in { $channels } { $channels ->
+        [one] Kanal
+       *[other] Kanälen
+    }
+status-shard-latency = Shard-Latenz
+status-shard = - Shard { $shard }: { $latency } ms
+status-no-heartbeats = Noch keine Heartbeats
+status-no-shards = Keine, Interaktionen kommen über den Endpunkt
+
+## /history
+
+history-unknown-user = Es war nicht erkennbar, wer diesen Befehl ausgeführt hat.
+history-unknown-presser = Es war nicht erkennbar, wer diesen Knopf gedrückt hat.
+history-on = Deine Suchen werden ab jetzt aufbewahrt. Mit `/history clear` kannst du sie jederzeit löschen.
+history-off = Deine Suchen werden nicht mehr aufbewahrt. Mit `/history clear` kannst du die bisherigen löschen.
+history-cleared = Dein Suchverlauf wurde gelöscht und ausgeschaltet.
+history-empty = Dein Verlauf ist noch leer.
+history-disabled = Dein Verlauf ist ausgeschaltet. Mit `/history on` werden deine Suchen aufbewahrt.
+history-title = Suchverlauf
+history-page = Seite { $page } von { $pages }
+history-no-results = Keine Ergebnisse
+history-rerun = { $number } wiederholen
+history-previous = Zurück
+history-next = Weiter
+history-gone = Diese Suche ist nicht mehr in deinem Verlauf.
+history-stale-button = Dieser Knopf macht nichts mehr.
+history-expired = Das Bild war ein Discord-Anhang, nach dem nicht mehr gesucht werden kann, sobald sein Link abgelaufen ist.
+
+## /admin
+
+admin-owner-only = Nur der Besitzer des Bots kann diesen Befehl verwenden.
+admin-no-gateway = Ohne Gateway gibt es keine Präsenz zu ändern.
+admin-reloaded = `config.toml` wurde neu geladen.
+admin-reload-failed = Die aktuelle Konfiguration wurde beibehalten, da `config.toml` nicht geladen werden konnte:
+admin-rate-limits-reset = Die SauceNao-Limits wurden zurückgesetzt.
+admin-rate-limits-set = SauceNao erlaubt jetzt { $short } Suchen alle 30 Sekunden und { $long } alle 24 Stunden.
+admin-backend-enabled = { $backend } ist jetzt eingeschaltet.
+admin-backend-disabled = { $backend } ist jetzt ausgeschaltet.
+admin-registered = Die Slash-Befehle wurden registriert.
+admin-cache-cleared = Der Zustand der Backend-Prüfungen und Circuit Breaker wurde zurückgesetzt.
+admin-errors-title = Letzte Fehler
+admin-errors-none = Keine Fehler seit dem Start des Bots.
+admin-presence-set = Die Präsenz ist jetzt `{ $presence }`.
+admin-presence-reset = Zurück zur Präsenz aus `config.toml`.
+
+## Befehle
+
+command-help = hilfe
+    .description = Hilfe zu SauceBot
+command-help-command = befehl
+    .description = Ein Befehl, zu dem Details angezeigt werden
+command-issue =
+    .description = Wie man ein Problem meldet
+command-support =
+    .description = Wie man die Weiterentwicklung von SauceBot unterstützen kann
+command-invite = einladen
+    .description = Gibt einen Einladungslink für den Bot
+
+command-saucenao =
+    .description = Sucht mit dem SauceNao-Backend nach einem Link oder Anhang.
+command-saucenao-link =
+    .description = Der Link, nach dem gesucht wird.
+command-saucenao-attachment = anhang
+    .description = Ein Anhang, nach dem gesucht wird
+command-saucenao-ephemeral = versteckt
+    .description = Ob die Nachricht versteckt sein soll
+command-saucenao-preprocess = vorbereiten
+    .description = Schneidet erst Ränder und Bildschirm-UI ab, oder teilt eine Collage auf
+
+command-iqdb =
+    .description = Sucht mit dem IQDB-Backend nach einem Link oder Anhang.
+command-iqdb-link =
+    .description = Der Link, nach dem gesucht wird.
+command-iqdb-attachment = anhang
+    .description = Ein Anhang, nach dem gesucht wird
+command-iqdb-ephemeral = versteckt
+    .description = Ob die Nachricht versteckt sein soll
+command-iqdb-preprocess = vorbereiten
+    .description = Schneidet erst Ränder und Bildschirm-UI ab, oder teilt eine Collage auf
+
+command-fuzzysearch =
+    .description = Sucht mit dem FuzzySearch-Backend nach einem Link oder Anhang.
+command-fuzzysearch-link =
+    .description = Der Link, nach dem gesucht wird.
+command-fuzzysearch-attachment = anhang
+    .description = Ein Anhang, nach dem gesucht wird
+command-fuzzysearch-ephemeral = versteckt
+    .description = Ob die Nachricht versteckt sein soll
+command-fuzzysearch-preprocess = vorbereiten
+    .description = Schneidet erst Ränder und Bildschirm-UI ab, oder teilt eine Collage auf
+
+command-batch = stapel
+    .description = Sucht nach mehreren Links oder Anhängen auf einmal, mit einem Ergebnis pro Bild.
+command-batch-backend =
+    .description = Das Backend, mit dem gesucht wird
+command-batch-links =
+    .description = Links, nach denen gesucht wird, durch Leerzeichen getrennt
+command-batch-attachment = anhang
+    .description = Ein Anhang, nach dem gesucht wird
+command-batch-attachment2 = anhang2
+    .description = Noch ein Anhang, nach dem gesucht wird
+command-batch-attachment3 = anhang3
+    .description = Noch ein Anhang, nach dem gesucht wird
+command-batch-attachment4 = anhang4
+    .description = Noch ein Anhang, nach dem gesucht wird
+command-batch-ephemeral = versteckt
+    .description = Ob die Nachrichten versteckt sein sollen
+
+command-status =
+    .description = Zeigt, ob die Backends erreichbar sind und wie viel SauceNao-Kontingent übrig ist
+
+command-history = verlauf
+    .description = Deine bisherigen Suchen, wenn du den Verlauf eingeschaltet hast
+command-history-list = liste
+    .description = Listet deine bisherigen Suchen auf, die neuesten zuerst
+command-history-list-page = seite
+    .description = Die Seite, mit der begonnen wird
+command-history-on = an
+    .description = Fängt an, deine Suchen aufzubewahren
+command-history-off = aus
+    .description = Hört auf, deine Suchen aufzubewahren, ohne sie zu löschen
+command-history-clear = löschen
+    .description = Löscht deinen Suchverlauf und schaltet ihn aus
+
+command-admin =
+    .description = Befehle, mit denen der Besitzer den Bot im laufenden Betrieb verwaltet
+command-admin-reload = neu-laden
+    .description = Liest config.toml neu ein
+command-admin-ratelimit-reset = limits-zurücksetzen
+    .description = Gibt den SauceNao-Limits ihr volles Kontingent zurück
+command-admin-ratelimit-set = limits-setzen
+    .description = Ändert, wie viele SauceNao-Suchen pro Zeitfenster erlaubt sind
+command-admin-ratelimit-set-short = kurz
+    .description = Erlaubte Suchen alle 30 Sekunden
+command-admin-ratelimit-set-long = lang
+    .description = Erlaubte Suchen alle 24 Stunden
+command-admin-backend =
+    .description = Schaltet ein Backend ein oder aus
+command-admin-backend-backend =
+    .description = Das Backend, das geändert wird
+command-admin-backend-enabled = aktiv
+    .description = Ob das Backend benutzt werden soll
+command-admin-register = registrieren
+    .description = Registriert die Slash-Befehle erneut bei Discord
+command-admin-clear-cache = cache-leeren
+    .description = Vergisst Health-Checks, Latenzen und Circuit Breaker der Backends
+command-admin-errors = fehler
+    .description = Zeigt die letzten fehlgeschlagenen Befehle
+command-admin-presence = präsenz
+    .description = Zeigt einen Status deiner Wahl, oder wieder die konfigurierten
+command-admin-presence-text =
+    .description = Was angezeigt wird, mit {"{"}guilds{"}"}, {"{"}searches_today{"}"} und {"{"}saucenao_remaining{"}"} ausgefüllt
+command-admin-presence-activity = aktivität
+    .description = Was der Bot gerade tut
+command-admin-presence-status =
+    .description = Ob der Bot als online, abwesend usw. angezeigt wird
+
+choice-preprocess-crop = Ränder und Bildschirm-UI abschneiden
+choice-preprocess-split = Zuschneiden, dann Collagen in Kacheln aufteilen
+choice-activity-playing = Spielt
+choice-activity-listening = Hört zu
+choice-activity-watching = Schaut
+choice-activity-competing = Nimmt teil an
+choice-status-online = Online
+choice-status-idle = Abwesend
+choice-status-dnd = Bitte nicht stören
+choice-status-invisible = Unsichtbar
@@ -0,0 +1,173 @@
+# Everything the bot says, in English. Other locales fall back to this for
+# anything they don't translate.
+
+## Searching
+
+invalid-link = Invalid link provided
+invalid-attachment = Invalid attachment provided
+video-attachment = Videos can't be searched for. Try a GIF, or a screenshot of the frame you're after.
+no-image = No image was provided, whether by link or attachment.
+
+searching-frames = That's an animation, so searching for { $count } of its frames instead.
+searching-tiles = That looks like a collage, so searching for each of its { $count } tiles instead.
+searching-cropped = Searching for the image with its borders cropped off instead.
+
+results-title = Results
+results-original = Original Link
+results-original-hidden = Not shown, as it isn't a web link.
+results-none = Found zero results
+results-none-description = Unable to find any results for the given link.
+results-similarity = Similarity: { $similarity }
+results-backend = Searched with { $backend }
+
+batch-too-many = That's { $count } images, but only up to { $max } can be searched at once.
+batch-failed = Image { $number } (<{ $link }>): { $reason }
+batch-failed-hidden = Image { $number }: { $reason }
+
+## SauceNao rate limits
+
+saucenao-limited-short = You are being rate limited. Please wait up to 30 seconds before trying again. (sorry, the rate limits on SauceNao are like this. Consider `/support`ing the bot's creator)
+saucenao-limited-long = You are being rate limited. Please wait up to 24 hours for it to fix. (sorry, the rate limits on SauceNao are like this. Consider `/support`ing the bot's creator)
+saucenao-queued = SauceNao is busy, so your search is waiting its turn. Position in the queue: { $position }
+saucenao-queue-searching = It's your turn, searching now.
+
+## /help, /issue, /support and /invite
+
+help-title = Help
+help-description = All commands are slash commands. Searches might take a few seconds, as they call a potentially slow web service. Use `/help <command>` for details on one command.
+help-commands = Commands
+help-backends = Backends
+help-backend-limit = { $available }/{ $max } searches per { $window } left, resets in { $resets_in }
+help-backend-unlimited = No limits on the bot's side
+help-settings = Settings
+help-settings-description =
+    - Links shown per search: up to { $top_links }
+    - Search history, if turned on, is kept for { $retention_days } days, up to { $max_entries } searches
+help-options = Options
+help-subcommands = Subcommands
+help-required = required
+help-usage = Usage
+help-cooldown = Can be used once every { $cooldown }.
+help-anywhere = Works in servers and DMs.
+help-guild-only = Only works in servers.
+help-dm-only = Only works in DMs.
+help-installed-guild-only = Only works in servers the bot was added to.
+help-hidden = Replies are hidden.
+help-unknown-command = There's no `/{ $command }` command. Use `/help` for the full list.
+
+issue = To report an issue, please go to <{ $url }>
+
+support-title = Support
+support-description = All the ways to support SauceBot.
+support-link = Link
+
+invite = To invite the bot to your server, please go to <{ $url }>
+
+## Errors
+
+error-unknown = Something went wrong while running this command.
+error-id = Error ID: `{ $id }`
+error-backend = { $backend }: { $explanation }
+error-backend-unavailable = { $backend } is temporarily disabled after failing repeatedly. It will be tried again in about { $minutes } { $minutes ->
+        [one] minute
+       *[other] minutes
+    }, until then please use a different backend.
+error-backend-disabled = { $backend } has been turned off by the bot's operator. Please use a different backend.
+error-backend-down = { $backend } is currently down. The bot checks on it regularly and will re-enable it by itself once it's back, until then please use a different backend.
+
+failure-bad-key = The search backend rejected the bot's API key. This is a problem on our end, please report it with `/issue`.
+failure-unsupported = The search backend couldn't read that image. Make sure the link points directly to a PNG, JPEG, GIF or WebP that isn't too large.
+failure-rate-limited = The search backend is rate limiting the bot right now. Please try again in a little while.
+failure-timeout = The search backend took too long to answer. Please try again in a little while.
+failure-down = The search backend seems to be down at the moment. Please try again later, or use a different backend.
+failure-unknown = The search backend returned an error we didn't expect.
+
+only-in-guilds = This command can only be used in a server.
+only-in-dms = This command can only be used in DMs.
+only-in-installed-guilds = This command can only be used in servers the bot was added to.
+missing-permissions = I'm missing permissions in this channel to do that: { $permissions }.
+cooldown = You're using `/{ $command }` too quickly. Please try again in { $seconds } { $seconds ->
+        [one] second
+       *[other] seconds
+    }.
+
+## Backends, as shown by /status and /help
+
+backend-disabled = Disabled by the operator
+backend-open = Disabled for about { $minutes } more { $minutes ->
+        [one] minute
+       *[other] minutes
+    } after repeated failures
+backend-down = Down, failed its last health check
+backend-down-checked = Down, failed its last health check { $minutes } { $minutes ->
+        [one] minute
+       *[other] minutes
+    } ago
+backend-available = Available
+backend-latency = Available, last answered in { $seconds }s
+
+## /status
+
+status-title = Status
+status-quota = SauceNao quota
+status-quota-value =
+    - Short: { $short_available }/{ $short_max } left, resets in { $short_resets_in }
+    - Long: { $long_available }/{ $long_max } left, resets in { $long_resets_in }
+status-uptime = Uptime
+status-servers = Servers
+status-servers-unknown = Unknown
+status-cache = Cache
+status-cache-searched = - Recent searches: { $links } { $links ->
+        [one] link
+       *[other] links
+    } from { $users } { $users ->
+        [one] user
+       *[other] users
+    }
+status-cache-posted = - Posted images: { $links } { $links ->
+        [one] link
+       *[other] links
+    } in { $channels } { $channels ->
+        [one] channel
+       *[other] channels
+    }
+status-shard-latency = Shard latency
+status-shard = - Shard { $shard }: { $latency }ms
+status-no-heartbeats = No heartbeats yet
+status-no-shards = None, interactions come through the endpoint
+
+## /history
+
+history-unknown-user = Couldn't tell who ran this command.
+history-unknown-presser = Couldn't tell who pressed this button.
+history-on = Your searches will now be kept. Use `/history clear` to delete them at any time.
+history-off = Your searches won't be kept anymore. Use `/history clear` to delete the ones already there.
+history-cleared = Deleted your search history, and turned it off.
+history-empty = Nothing in your history yet.
+history-disabled = Your history is off. Use `/history on` to start keeping your searches.
+history-title = Search history
+history-page = Page { $page } of { $pages }
+history-no-results = No results
+history-rerun = Re-run { $number }
+history-previous = Previous
+history-next = Next
+history-gone = That search isn't in your history anymore.
+history-stale-button = This button doesn't do anything anymore.
+history-expired = That image was a Discord attachment, which can't be searched for again once its link expires.
+
+## /admin
+
+admin-owner-only = Only the bot's owner can use this command.
+admin-no-gateway = There's no presence to change without the gateway.
+admin-reloaded = Reloaded `config.toml`.
+admin-reload-failed = Kept the current config, as `config.toml` couldn't be loaded:
+admin-rate-limits-reset = Reset the SauceNao rate limits.
+admin-rate-limits-set = SauceNao now allows { $short } searches every 30 seconds and { $long } every 24 hours.
+admin-backend-enabled = { $backend } is now enabled.
+admin-backend-disabled = { $backend } is now disabled.
+admin-registered = Registered the slash commands.
+admin-cache-cleared = Cleared the backend health and circuit breaker state.
+admin-errors-title = Recent errors
+admin-errors-none = No errors since the bot started.
+admin-presence-set = The presence is now `{ $presence }`.
+admin-presence-reset = Went back to the presence from `config.toml`.
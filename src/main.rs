@@ -1,108 +1,69 @@
 #![warn(clippy::pedantic, clippy::nursery, clippy::perf)]
 #![deny(clippy::unwrap_used, clippy::panic)]
 
-use sparkle_convenience::Bot;
-use std::{env, sync::Arc};
-use tokio::task::JoinSet;
-use tracing::{error, info, warn};
-use tracing_subscriber::{EnvFilter, fmt};
-use twilight_gateway::{Event, EventTypeFlags, Intents, Shard, StreamExt as _};
-use twilight_model::gateway::{
-    payload::outgoing::UpdatePresence,
-    presence::{Activity, ActivityType, MinimalActivity, Status},
-};
-
-mod commands;
-mod config;
-mod events;
-mod handle;
-mod rate_limiter;
-mod sauce_finder;
-
-async fn handle_event(event: Event, bot: Arc<Bot>) -> Res<()> {
-    let res = match event {
-        Event::InteractionCreate(interaction) => events::interaction_create(bot, interaction).await,
-
-        _ => Ok(()),
-    };
+use std::{env, path::PathBuf};
 
-    if let Err(e) = res {
-        error!("Error handling event: {e}");
-    }
+use color_eyre::eyre::eyre;
+use sauce_bot::{Config, Res, SauceBot};
+use tracing_subscriber::{EnvFilter, fmt};
 
-    Ok(())
-}
+const USAGE: &str = "Usage: sauce_bot [--config <path>]
 
-type Res<T> = color_eyre::Result<T>;
+Options:
+  -c, --config <path>  Use the config file at <path> instead of ./config.toml
+  -h, --help           Print this message
+  -V, --version        Print the version";
 
 #[tokio::main]
 async fn main() -> Res<()> {
-    setup()?;
-
-    let cfg = config::Config::load();
-    let token = cfg.credentials().token();
-
-    let (bot, shards) = Bot::new(
-        token.clone(),
-        Intents::empty(),
-        EventTypeFlags::INTERACTION_CREATE | EventTypeFlags::READY,
-    )
-    .await?;
-    let bot = Arc::new(bot);
-
-    info!("Starting...");
+    let Some(args) = Args::parse(env::args().skip(1))? else {
+        return Ok(());
+    };
 
-    // let mut senders = Vec::with_capacity(shards.len());
-    let mut tasks = Vec::with_capacity(shards.len());
+    setup()?;
 
-    for shard in shards {
-        // senders.push(shard.sender());
-        tasks.push(tokio::spawn(runner(bot.clone(), shard)));
+    if let Some(path) = args.config {
+        Config::use_path(path);
     }
 
-    let joiners = JoinSet::from_iter(tasks);
-
-    joiners.join_all().await;
-
-    Ok(())
+    SauceBot::new(Config::current()).await?.run().await
 }
 
-async fn runner(bot: Arc<Bot>, mut shard: Shard) {
-    while let Some(event) = shard.next_event(bot.event_type_flags).await {
-        let event = match event {
-            Ok(event) => event,
-            Err(error) => {
-                warn!(?error, "Error receiving event");
-
-                continue;
-            }
-        };
-
-        match event {
-            Event::Ready(event) => {
-                let activity = Activity::from(MinimalActivity {
-                    kind: ActivityType::Playing,
-                    name: "/help - slash commands!".to_string(),
-                    url: None,
-                });
-
-                let command = UpdatePresence::new(vec![activity], false, None, Status::Online)
-                    .expect("valid");
-
-                shard.command(&command);
-
-                info!(
-                    "Shard {} ready, logged in as {}",
-                    shard.id(),
-                    event.user.name
-                );
+#[derive(Debug, Default)]
+struct Args {
+    config: Option<PathBuf>,
+}
 
-                let _ = events::ready(bot.clone()).await;
-            }
-            _ => {
-                tokio::spawn(handle_event(event, bot.clone()));
+impl Args {
+    /// Returns `None` if the program should exit right away, e.g. after
+    /// printing help.
+    fn parse(mut args: impl Iterator<Item = String>) -> Res<Option<Self>> {
+        let mut res = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" | "--config" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("`{arg}` needs a path\n\n{USAGE}"))?;
+
+                    res.config = Some(PathBuf::from(path));
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+
+                    return Ok(None);
+                }
+                "-V" | "--version" => {
+                    println!("sauce_bot {}", env!("CARGO_PKG_VERSION"));
+
+                    return Ok(None);
+                }
+                _ => return Err(eyre!("Unknown argument `{arg}`\n\n{USAGE}")),
             }
         }
+
+        Ok(Some(res))
     }
 }
 
@@ -0,0 +1,225 @@
+//! Cleans images up before they're searched for: trims borders and screenshot
+//! chrome, and optionally splits collages into their tiles.
+
+use std::{io::Cursor, ops::Range};
+
+use image::{ImageFormat, Rgba, RgbaImage, imageops};
+
+use crate::{Res, frames};
+
+/// How far apart two channels can be for pixels to count as the same colour.
+const TOLERANCE: u8 = 24;
+
+/// Share of a line that has to match for it to count as screenshot chrome,
+/// e.g. a status bar with a few icons on it.
+const CHROME: f32 = 0.9;
+
+/// Share of a line that has to match for it to count as a gutter between the
+/// tiles of a collage.
+const GUTTER: f32 = 0.98;
+
+/// The main region has to be at least this share of the image to be cropped
+/// to, so a mostly plain image isn't cropped down to a sliver.
+const MIN_REGION: f32 = 0.25;
+
+/// Tiles smaller than this share of the image are taken to be noise.
+const MIN_TILE: f32 = 0.15;
+
+const MAX_TILES: usize = 9;
+
+/// Downloads `link` and cleans it up, returning the PNGs to search for
+/// instead. Empty if there was nothing to change.
+pub async fn run(link: &str, split: bool) -> Res<Vec<Vec<u8>>> {
+    let Some(bytes) = frames::download(link).await? else {
+        return Ok(Vec::new());
+    };
+
+    tokio::task::spawn_blocking(move || preprocess(&bytes, split)).await?
+}
+
+/// Crops the image in `bytes` to its main region, then splits it into tiles if
+/// `split` is set and it looks like a collage. Empty if there was nothing to
+/// change.
+pub fn preprocess(bytes: &[u8], split: bool) -> Res<Vec<Vec<u8>>> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    let dimensions = image.dimensions();
+
+    // Cropping to the main region would keep just one tile of a collage, so
+    // only its outer border is trimmed before splitting.
+    let tiles = if split {
+        tiles(&crop_to(image.clone(), GUTTER, within_borders))
+    } else {
+        Vec::new()
+    };
+
+    let images = if tiles.is_empty() {
+        let cropped = crop_to(image, CHROME, main_region);
+
+        if cropped.dimensions() == dimensions {
+            return Ok(Vec::new());
+        }
+
+        vec![cropped]
+    } else {
+        tiles
+    };
+
+    images
+        .into_iter()
+        .map(|image| {
+            let mut png = Cursor::new(Vec::new());
+            image.write_to(&mut png, ImageFormat::Png)?;
+
+            Ok(png.into_inner())
+        })
+        .collect()
+}
+
+fn same(a: Rgba<u8>, b: Rgba<u8>) -> bool {
+    a.0.iter().zip(b.0).all(|(a, b)| a.abs_diff(b) <= TOLERANCE)
+}
+
+/// Whether at least `share` of the line is the same colour as its first pixel.
+#[allow(clippy::cast_precision_loss)]
+fn plain(mut line: impl Iterator<Item = Rgba<u8>>, share: f32) -> bool {
+    let Some(first) = line.next() else {
+        return true;
+    };
+
+    let (mut matching, mut total) = (1_u32, 1_u32);
+    for pixel in line {
+        total += 1;
+        matching += u32::from(same(first, pixel));
+    }
+
+    matching as f32 >= total as f32 * share
+}
+
+fn plain_rows(image: &RgbaImage, share: f32) -> Vec<bool> {
+    (0..image.height())
+        .map(|y| plain((0..image.width()).map(|x| *image.get_pixel(x, y)), share))
+        .collect()
+}
+
+fn plain_columns(image: &RgbaImage, share: f32) -> Vec<bool> {
+    (0..image.width())
+        .map(|x| plain((0..image.height()).map(|y| *image.get_pixel(x, y)), share))
+        .collect()
+}
+
+/// The runs of lines that aren't plain.
+fn runs(plain: &[bool]) -> Vec<Range<u32>> {
+    let mut res = Vec::new();
+    let mut start = None;
+
+    for (n, &plain) in (0..).zip(plain) {
+        match (start, plain) {
+            (None, false) => start = Some(n),
+            (Some(s), true) => {
+                res.push(s..n);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        res.push(s..u32::try_from(plain.len()).unwrap_or(u32::MAX));
+    }
+
+    res
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn share_of(length: u32, share: f32) -> u32 {
+    (length as f32 * share) as u32
+}
+
+/// The longest run of lines that aren't plain, if it's big enough to be the
+/// main region.
+fn main_region(plain: &[bool], length: u32) -> Option<Range<u32>> {
+    runs(plain)
+        .into_iter()
+        .max_by_key(|run| run.end - run.start)
+        .filter(|run| run.end - run.start >= share_of(length, MIN_REGION))
+}
+
+/// Where the lines that aren't plain start and end, leaving out borders.
+fn within_borders(plain: &[bool], _length: u32) -> Option<Range<u32>> {
+    let runs = runs(plain);
+
+    Some(runs.first()?.start..runs.last()?.end)
+}
+
+/// Crops `image` to `region` of its rows, then of its columns.
+fn crop_to(
+    image: RgbaImage,
+    share: f32,
+    region: fn(&[bool], u32) -> Option<Range<u32>>,
+) -> RgbaImage {
+    let (width, height) = image.dimensions();
+
+    let image = match region(&plain_rows(&image, share), height) {
+        Some(rows) if rows != (0..height) => {
+            imageops::crop_imm(&image, 0, rows.start, width, rows.end - rows.start).to_image()
+        }
+        _ => image,
+    };
+
+    let (width, height) = image.dimensions();
+
+    match region(&plain_columns(&image, share), width) {
+        Some(columns) if columns != (0..width) => imageops::crop_imm(
+            &image,
+            columns.start,
+            0,
+            columns.end - columns.start,
+            height,
+        )
+        .to_image(),
+        _ => image,
+    }
+}
+
+/// Splits a collage along its gutters, row by row. Empty if it doesn't look
+/// like a collage.
+fn tiles(image: &RgbaImage) -> Vec<RgbaImage> {
+    let (width, height) = image.dimensions();
+    let mut res = Vec::new();
+
+    for rows in runs(&plain_rows(image, GUTTER)) {
+        if rows.end - rows.start < share_of(height, MIN_TILE) {
+            continue;
+        }
+
+        let band =
+            imageops::crop_imm(image, 0, rows.start, width, rows.end - rows.start).to_image();
+
+        for columns in runs(&plain_columns(&band, GUTTER)) {
+            if columns.end - columns.start < share_of(width, MIN_TILE) {
+                continue;
+            }
+
+            res.push(
+                imageops::crop_imm(
+                    &band,
+                    columns.start,
+                    0,
+                    columns.end - columns.start,
+                    band.height(),
+                )
+                .to_image(),
+            );
+        }
+    }
+
+    if res.len() < 2 || res.len() > MAX_TILES {
+        return Vec::new();
+    }
+
+    res
+}
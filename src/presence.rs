@@ -0,0 +1,184 @@
+//! What the shards show as the bot's status: the messages under
+//! `[settings.presence]` in turn, or the one `/admin presence` set in their
+//! place.
+
+use std::{
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use sparkle_convenience::Bot;
+use tokio::{sync::Notify, time::sleep};
+use tracing::warn;
+use twilight_model::gateway::{
+    OpCode,
+    payload::outgoing::{UpdatePresence, update_presence::UpdatePresencePayload},
+    presence::MinimalActivity,
+};
+
+use crate::{
+    backend,
+    commands::saucenao,
+    config::{Config, PresenceMessage, PresenceStatus},
+    shards,
+};
+
+/// What `/admin presence` set, shown instead of the configured messages.
+static OVERRIDE: Mutex<Option<(PresenceMessage, Option<PresenceStatus>)>> = Mutex::new(None);
+
+/// The presence last shown, for shards that connect later on.
+static CURRENT: Mutex<Option<UpdatePresencePayload>> = Mutex::new(None);
+
+/// Which of the configured messages is next.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// Woken when the override changes, to show it straight away.
+static CHANGED: Notify = Notify::const_new();
+
+/// How long the number of servers is reused before it's asked for again.
+const GUILD_COUNT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The number of servers last fetched, and when.
+static GUILD_COUNT: Mutex<Option<(Instant, u64)>> = Mutex::new(None);
+
+/// About how many servers the bot is in, fetched at most every
+/// [`GUILD_COUNT_TTL`]. When fetching fails, the last known number is used,
+/// if there is one.
+pub async fn guild_count(bot: &Bot) -> Option<u64> {
+    let cached = *GUILD_COUNT.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((fetched_at, count)) = cached
+        && fetched_at.elapsed() < GUILD_COUNT_TTL
+    {
+        return Some(count);
+    }
+
+    let fetched = match bot.http.current_user_application().await {
+        Ok(response) => response
+            .model()
+            .await
+            .ok()
+            .and_then(|application| application.approximate_guild_count),
+        Err(e) => {
+            warn!(?e, "Unable to get the number of servers");
+
+            None
+        }
+    };
+
+    match fetched {
+        Some(count) => {
+            *GUILD_COUNT.lock().unwrap_or_else(PoisonError::into_inner) =
+                Some((Instant::now(), count));
+
+            Some(count)
+        }
+        None => cached.map(|(_, count)| count),
+    }
+}
+
+/// The presence last shown, if there is one.
+pub fn current() -> Option<UpdatePresencePayload> {
+    CURRENT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Shows `message` instead of the configured ones, with `status` if given,
+/// until it's cleared with `None`.
+pub fn set_override(message: Option<(PresenceMessage, Option<PresenceStatus>)>) {
+    *OVERRIDE.lock().unwrap_or_else(PoisonError::into_inner) = message;
+
+    CHANGED.notify_one();
+}
+
+/// Moves on to the next presence every `rotate_secs`, or as soon as the
+/// override changes.
+pub async fn rotate(bot: &Bot) {
+    loop {
+        let interval = Config::current().settings().presence().rotate_interval();
+
+        tokio::select! {
+            () = sleep(interval) => {}
+            () = CHANGED.notified() => {}
+        }
+
+        advance(bot).await;
+    }
+}
+
+/// Shows the override, or the next configured message, on every shard.
+pub async fn advance(bot: &Bot) {
+    let cfg = Config::current();
+    let settings = cfg.settings().presence();
+
+    let overridden = OVERRIDE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    let (message, status) = match overridden {
+        Some((message, status)) => (message, status.unwrap_or(settings.status())),
+        None => {
+            let messages = settings.messages();
+            if messages.is_empty() {
+                return;
+            }
+
+            let next = NEXT.fetch_add(1, Ordering::Relaxed) % messages.len();
+
+            (messages[next].clone(), settings.status())
+        }
+    };
+
+    let activity = MinimalActivity {
+        kind: message.activity.into(),
+        name: render(bot, &message.text).await,
+        url: None,
+    };
+
+    let payload = match UpdatePresencePayload::new(vec![activity.into()], false, None, status) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!(?e, "Unable to make a presence out of {message:?}");
+
+            return;
+        }
+    };
+
+    *CURRENT.lock().unwrap_or_else(PoisonError::into_inner) = Some(payload.clone());
+
+    shards::command(&UpdatePresence {
+        d: payload,
+        op: OpCode::PresenceUpdate,
+    });
+}
+
+/// `text` with the values it mentions filled in.
+pub async fn render(bot: &Bot, text: &str) -> String {
+    let mut text = text.to_owned();
+
+    if text.contains("{guilds}") {
+        text = text.replace(
+            "{guilds}",
+            &guild_count(bot)
+                .await
+                .map_or_else(|| "?".to_owned(), |guilds| guilds.to_string()),
+        );
+    }
+
+    if text.contains("{searches_today}") {
+        text = text.replace("{searches_today}", &backend::searches_today().to_string());
+    }
+
+    if text.contains("{saucenao_remaining}") {
+        let (_, long) = saucenao::quota().await;
+
+        text = text.replace("{saucenao_remaining}", &long.available().to_string());
+    }
+
+    text
+}
@@ -35,4 +35,44 @@ impl RateLimiter {
     pub const fn remaining(&self) -> u64 {
         self.executions_left_in_window
     }
+
+    /// Like [`Self::remaining`], but accounts for a window that has already
+    /// run out and will be reset on the next attempt.
+    pub fn available(&self) -> u64 {
+        if self.window_start_time.elapsed() > self.window_duration {
+            self.max_executions_per_window
+        } else {
+            self.executions_left_in_window
+        }
+    }
+
+    /// Starts a fresh window with the full allowance.
+    pub fn reset(&mut self) {
+        self.window_start_time = Instant::now();
+        self.executions_left_in_window = self.max_executions_per_window;
+    }
+
+    /// Changes the allowance, keeping whatever was already used up in the
+    /// current window.
+    pub const fn set_max(&mut self, max: u64) {
+        let used = self
+            .max_executions_per_window
+            .saturating_sub(self.executions_left_in_window);
+
+        self.max_executions_per_window = max;
+        self.executions_left_in_window = max.saturating_sub(used);
+    }
+
+    pub const fn max(&self) -> u64 {
+        self.max_executions_per_window
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window_duration
+    }
+
+    pub fn resets_in(&self) -> Duration {
+        self.window_duration
+            .saturating_sub(self.window_start_time.elapsed())
+    }
 }
@@ -0,0 +1,131 @@
+//! Links worth suggesting when someone starts typing in a `link` option.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use twilight_model::{
+    channel::Message,
+    id::{
+        Id,
+        marker::{ChannelMarker, UserMarker},
+    },
+};
+
+/// How many links are remembered per user, and per channel.
+const KEPT: usize = 10;
+
+/// Discord won't accept a longer string as an autocomplete choice.
+const MAX_CHOICE_LEN: usize = 100;
+
+/// Discord shows at most this many autocomplete choices.
+const MAX_CHOICES: usize = 25;
+
+static SEARCHED: LazyLock<Mutex<HashMap<Id<UserMarker>, VecDeque<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static POSTED: LazyLock<Mutex<HashMap<Id<ChannelMarker>, VecDeque<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn remember<K: Eq + Hash>(map: &Mutex<HashMap<K, VecDeque<String>>>, key: K, link: &str) {
+    let mut map = map.lock().unwrap_or_else(PoisonError::into_inner);
+    let links = map.entry(key).or_default();
+
+    links.retain(|l| l != link);
+    links.push_front(link.to_owned());
+    links.truncate(KEPT);
+}
+
+/// How many keys `map` has, and how many links it holds in all.
+fn size<K>(map: &Mutex<HashMap<K, VecDeque<String>>>) -> (usize, usize) {
+    let map = map.lock().unwrap_or_else(PoisonError::into_inner);
+
+    (map.len(), map.values().map(VecDeque::len).sum())
+}
+
+/// How many users have recent searches remembered, and how many links that is.
+pub fn searched_size() -> (usize, usize) {
+    size(&SEARCHED)
+}
+
+/// How many channels have recently posted images remembered, and how many
+/// links that is.
+pub fn posted_size() -> (usize, usize) {
+    size(&POSTED)
+}
+
+/// Remembers that `user` searched for `link`.
+pub fn searched(user: Id<UserMarker>, link: &str) {
+    remember(&SEARCHED, user, link);
+}
+
+/// Forgets the links `user` searched for.
+pub fn forget(user: Id<UserMarker>) {
+    SEARCHED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&user);
+}
+
+/// Remembers the images in `message`, if there are any. Only called when
+/// `cache_messages` is turned on in the config.
+pub fn posted(message: &Message) {
+    let attachments = message
+        .attachments
+        .iter()
+        .filter(|a| {
+            a.content_type
+                .as_ref()
+                .is_some_and(|t| t.starts_with("image/"))
+        })
+        .map(|a| a.url.as_str());
+    let embeds = message
+        .embeds
+        .iter()
+        .filter_map(|e| e.image.as_ref().map(|i| i.url.as_str()));
+
+    for link in attachments.chain(embeds) {
+        remember(&POSTED, message.channel_id, link);
+    }
+}
+
+/// `user`'s recent searches, then the images recently posted in `channel`,
+/// narrowed down to the ones containing what's been typed so far.
+pub fn suggestions(
+    user: Option<Id<UserMarker>>,
+    channel: Option<Id<ChannelMarker>>,
+    typed: &str,
+) -> Vec<String> {
+    let searched = user
+        .and_then(|user| {
+            SEARCHED
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&user)
+                .cloned()
+        })
+        .unwrap_or_default();
+    let posted = channel
+        .and_then(|channel| {
+            POSTED
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&channel)
+                .cloned()
+        })
+        .unwrap_or_default();
+
+    let mut res: Vec<String> = Vec::new();
+
+    for link in searched.into_iter().chain(posted) {
+        if link.len() <= MAX_CHOICE_LEN && link.contains(typed) && !res.contains(&link) {
+            res.push(link);
+        }
+    }
+
+    res.truncate(MAX_CHOICES);
+
+    res
+}
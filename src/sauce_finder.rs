@@ -1,125 +1,590 @@
-use crate::{Res, config::Config, handle::Handle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    Res,
+    backend::{self, Backend, SearchError},
+    commands::{PreprocessOption, saucenao},
+    config::Config,
+    error::Friendly,
+    events::{Command, Metadata},
+    frames,
+    handle::{Handle, quiet_reply},
+    history, i18n, preprocess, recent,
+};
 use color_eyre::eyre::eyre;
 use num_traits::FromPrimitive;
-use sauce_api::{error::Error, source::Output};
+use sauce_api::source::Output;
 use sparkle_convenience::reply::Reply;
-use tracing::error;
-use twilight_model::channel::{Attachment, message::embed::EmbedField};
-use twilight_util::builder::embed::EmbedBuilder;
+use tracing::{debug, info, warn};
+use twilight_model::{
+    channel::{Attachment, message::embed::EmbedField},
+    http::attachment::Attachment as Upload,
+    id::{Id, marker::UserMarker},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder, ImageSource};
 use url::Url;
 
-pub async fn get_link_from_link(handle: &Handle, link: String) -> Res<String> {
+pub fn get_link_from_link(link: String, locale: &str) -> Res<String> {
     if Url::parse(&link).is_err() {
-        handle
-            .reply(Reply::new().ephemeral().content("Invalid link provided"))
-            .await?;
-
-        return Err(eyre!("invalid link provided"));
+        return Err(Friendly::new(i18n::text(locale, "invalid-link", &[])).into());
     }
 
     Ok(link)
 }
 
-pub async fn get_link_from_attachment(handle: &Handle, attachment: Attachment) -> Res<String> {
-    if let Some(content_type) = attachment.content_type
-        && !content_type.starts_with("image/")
-    {
-        handle
-            .reply(
-                Reply::new()
-                    .ephemeral()
-                    .content("Invalid attachment provided"),
-            )
-            .await?;
+pub fn get_link_from_attachment(attachment: Attachment, locale: &str) -> Res<String> {
+    if let Some(content_type) = attachment.content_type {
+        if content_type.starts_with("video/") {
+            return Err(Friendly::new(i18n::text(locale, "video-attachment", &[])).into());
+        }
 
-        return Err(eyre!("invalid attachment provided"));
+        if !content_type.starts_with("image/") {
+            return Err(Friendly::new(i18n::text(locale, "invalid-attachment", &[])).into());
+        }
     }
 
     Ok(attachment.url)
 }
 
-pub async fn respond(
-    handle: Handle,
-    res: Result<Output, Error>,
-    cfg: Config,
+/// Where a search on `backend` goes: the backend to search first, then the
+/// fallbacks to try in turn. SauceNao has a search used up first, and once
+/// it's out, the search moves on to the fallbacks, or waits in the queue if
+/// there are none. `Err` explains why the search can't go ahead.
+pub(crate) async fn route(
+    handle: &Handle,
+    backend: Backend,
+    cfg: &Config,
     ephemeral: Option<bool>,
-) -> Res<()> {
-    if let Ok(result) = res {
-        let mut embed = EmbedBuilder::new()
-            .title("Results")
-            .color(0x8B_D8C6)
-            .field(EmbedField {
-                name: "Original Link".to_owned(),
-                value: result.original_url.clone(),
-                inline: false,
-            });
+) -> Res<Result<(Backend, Vec<Backend>), Reply>> {
+    let fallbacks = cfg.settings().fallbacks(backend);
 
-        if result.items.is_empty() {
-            embed = embed.field(EmbedField {
-                name: "Found zero results".to_owned(),
-                value: "Unable to find any results for the given link.".to_owned(),
-                inline: false,
-            });
-        } else {
-            let mut items = result.items;
+    if backend != Backend::SauceNao || saucenao::try_take_search().await {
+        return Ok(Ok((backend, fallbacks)));
+    }
 
-            items.sort_unstable_by_key(|c| i32::from_f32(c.similarity * 100f32));
+    if let Some((&first, rest)) = fallbacks.split_first() {
+        return Ok(Ok((first, rest.to_vec())));
+    }
+
+    Ok(match saucenao::queue_search(handle, ephemeral).await? {
+        None => Ok((backend, fallbacks)),
+        Some(reply) => Err(reply),
+    })
+}
 
-            for x in items.iter().rev().take(cfg.settings().top_links() as usize) {
-                embed = embed.field(EmbedField {
-                    name: format!("Similarity: {:0.2}", x.similarity),
-                    value: format!("**<{}>**", x.link),
-                    inline: false,
-                });
+/// Fails early if neither `backend` nor any of its fallbacks can be searched,
+/// with why `backend` can't.
+pub(crate) async fn ensure_available(backend: Backend, cfg: &Config) -> Result<(), SearchError> {
+    let Err(e) = backend::ensure_available(backend).await else {
+        return Ok(());
+    };
+
+    for fallback in cfg.settings().fallbacks(backend) {
+        if backend::ensure_available(fallback).await.is_ok() {
+            return Ok(());
+        }
+    }
+
+    Err(e)
+}
+
+/// Searches `backend` for `link`, then each of `fallbacks` in turn while they
+/// fail, returning which one answered, or why `backend` couldn't. A SauceNao
+/// fallback is skipped once it's out of searches.
+async fn search_link(
+    backend: Backend,
+    fallbacks: &[Backend],
+    cfg: &Config,
+    link: &str,
+) -> Result<(Backend, Output), SearchError> {
+    let error = match backend::search(backend, cfg, link).await {
+        Ok(output) => return Ok((backend, output)),
+        Err(e) => e,
+    };
+
+    for &fallback in fallbacks {
+        if fallback == Backend::SauceNao && !saucenao::try_take_search().await {
+            continue;
+        }
+
+        match backend::search(fallback, cfg, link).await {
+            Ok(output) => {
+                info!(%error, "Searched {link} with {fallback} instead");
+
+                return Ok((fallback, output));
             }
+            Err(e) => debug!(%e, "Falling back to {fallback} failed too"),
+        }
+    }
+
+    Err(error)
+}
+
+/// Searches `backend` and then `fallbacks` for `link`, or for a few of its
+/// frames if it's animated, returning which backend answered. Frames are
+/// posted as a reply first, as the backends only take links.
+pub(crate) async fn search(
+    handle: &Handle,
+    backend: Backend,
+    fallbacks: &[Backend],
+    cfg: &Config,
+    link: &str,
+    ephemeral: Option<bool>,
+) -> Result<(Backend, Output), SearchError> {
+    let frames = match frames::extract(link).await {
+        Ok(Some(frames)) => frames,
+        Ok(None) => return search_link(backend, fallbacks, cfg, link).await,
+        Err(e) => {
+            warn!(
+                ?e,
+                "Unable to extract frames from {link}, searching it as is"
+            );
+
+            return search_link(backend, fallbacks, cfg, link).await;
+        }
+    };
+
+    let content = i18n::text(
+        handle.locale(),
+        "searching-frames",
+        &[("count", frames.len().into())],
+    );
+
+    let links = match upload(handle, content, "frame", frames, ephemeral).await {
+        Ok(links) => links,
+        Err(e) => {
+            warn!(
+                ?e,
+                "Unable to upload frames from {link}, searching it as is"
+            );
+
+            return search_link(backend, fallbacks, cfg, link).await;
         }
+    };
+
+    let mut answered = None;
+    let mut failed = None;
+    let mut outputs = Vec::with_capacity(links.len());
+
+    for (n, frame) in links.iter().enumerate() {
+        // The command already routed the first frame, using up a SauceNao
+        // search. Later ones move on to the fallbacks once it's out.
+        let (backend, fallbacks) = if n == 0 || backend != Backend::SauceNao {
+            (backend, fallbacks.to_vec())
+        } else {
+            match route(handle, backend, cfg, ephemeral).await {
+                Ok(Ok(routed)) => routed,
+                Ok(Err(_)) => {
+                    debug!("Out of searches, leaving the rest of the frames of {link}");
 
-        let embed = embed.build();
+                    break;
+                }
+                Err(e) => {
+                    warn!(?e, "Unable to route the rest of the frames of {link}");
 
-        let mut reply = Reply::new().embed(embed);
+                    break;
+                }
+            }
+        };
 
-        if let Some(ephemeral) = ephemeral
-            && ephemeral
-        {
-            reply = reply.ephemeral();
+        // One frame failing shouldn't throw away what the others found.
+        match search_link(backend, &fallbacks, cfg, frame).await {
+            Ok((backend, output)) => {
+                // Shown as the one that answered the first frame.
+                answered.get_or_insert(backend);
+                outputs.push(output);
+            }
+            Err(e) => {
+                warn!(%e, "Unable to search frame {n} of {link}");
+
+                failed.get_or_insert(e);
+            }
         }
+    }
 
-        handle.reply(reply).await?;
-    } else if let Err(e) = res {
-        error!(?e, "Failed to execute");
+    if let (None, Some(e)) = (answered, failed) {
+        return Err(e);
+    }
 
-        handle
-            .reply(
-                Reply::new()
-                    .ephemeral()
-                    .content(format!("Failed to execute command: {e}")),
+    Ok((
+        answered.unwrap_or(backend),
+        frames::merge(link.to_owned(), outputs),
+    ))
+}
+
+/// Posts `images` as PNGs named after `name`, spoilered unless the channel is
+/// NSFW, returning where Discord put them.
+async fn upload(
+    handle: &Handle,
+    content: String,
+    name: &str,
+    images: Vec<Vec<u8>>,
+    ephemeral: Option<bool>,
+) -> Res<Vec<String>> {
+    let mut reply = quiet_reply().content(content);
+    reply.attachments = (0..)
+        .zip(images)
+        .map(|(id, image)| {
+            Upload::from_bytes(
+                spoiler_name(format!("{name}-{id}.png"), handle.nsfw()),
+                image,
+                id,
             )
-            .await?;
+        })
+        .collect();
+
+    if handle.ephemeral(ephemeral) {
+        reply = reply.ephemeral();
+    }
+
+    let message = handle
+        .reply(reply)
+        .await?
+        .ok_or_else(|| eyre!("Posting the images didn't return a message"))?;
+
+    Ok(message
+        .attachments
+        .into_iter()
+        .map(|attachment| attachment.url)
+        .collect())
+}
+
+/// The links to search for instead of `link` once it's been through
+/// `preprocess`, posted as a reply first. Just `link` if there was nothing to
+/// change, or preprocessing failed.
+async fn preprocessed(
+    handle: &Handle,
+    link: &str,
+    preprocess: Option<PreprocessOption>,
+    ephemeral: Option<bool>,
+) -> Vec<String> {
+    let Some(preprocess) = preprocess else {
+        return vec![link.to_owned()];
+    };
+
+    let images = match preprocess::run(link, preprocess == PreprocessOption::Split).await {
+        Ok(images) if !images.is_empty() => images,
+        Ok(_) => return vec![link.to_owned()],
+        Err(e) => {
+            warn!(?e, "Unable to preprocess {link}, searching it as is");
+
+            return vec![link.to_owned()];
+        }
+    };
+
+    let content = if images.len() > 1 {
+        i18n::text(
+            handle.locale(),
+            "searching-tiles",
+            &[("count", images.len().into())],
+        )
+    } else {
+        i18n::text(handle.locale(), "searching-cropped", &[])
+    };
+
+    match upload(handle, content, "preprocessed", images, ephemeral).await {
+        Ok(links) if !links.is_empty() => links,
+        Ok(_) => vec![link.to_owned()],
+        Err(e) => {
+            warn!(
+                ?e,
+                "Unable to upload preprocessed images from {link}, searching it as is"
+            );
+
+            vec![link.to_owned()]
+        }
+    }
+}
+
+/// Everything a search command does once it has a link: preprocesses it if
+/// asked to, then searches and responds once per resulting image.
+pub async fn find(
+    handle: Handle,
+    backend: Backend,
+    user: Option<Id<UserMarker>>,
+    link: &str,
+    ephemeral: Option<bool>,
+    preprocess: Option<PreprocessOption>,
+) -> Res<()> {
+    let cfg = Config::current();
+
+    // Sorted out before preprocessing, so being turned away replaces the
+    // "thinking..." message rather than the preprocessed images.
+    let mut first = Some(route(&handle, backend, &cfg, ephemeral).await?);
+
+    for (n, image) in preprocessed(&handle, link, preprocess, ephemeral)
+        .await
+        .into_iter()
+        .enumerate()
+    {
+        let routed = match first.take() {
+            Some(routed) => routed,
+            None => route(&handle, backend, &cfg, ephemeral).await?,
+        };
+
+        let (backend, fallbacks) = match routed {
+            Ok(routed) => routed,
+            Err(mut reply) => {
+                // Keep the results already posted.
+                reply.update_last = n == 0;
+
+                handle.reply(reply).await?;
+
+                break;
+            }
+        };
+
+        let res = search(&handle, backend, &fallbacks, &cfg, &image, ephemeral).await;
+
+        // Kept as the link the user gave, as preprocessed images are the bot's
+        // own uploads, which expire.
+        if let Ok((backend, output)) = &res {
+            history::record(user, *backend, link, output);
+        }
+
+        respond(handle.clone(), res, cfg.clone(), ephemeral).await?;
     }
 
     Ok(())
 }
 
-pub async fn respond_failure(handle: Handle) -> Res<()> {
-    let reply = Reply::new()
-        .ephemeral()
-        .content("No image was provided, whether by link or attachment.");
+/// The options every search command takes.
+pub struct SearchOptions<'a> {
+    pub link: Option<&'a String>,
+    pub attachment: Option<&'a Attachment>,
+    pub ephemeral: Option<bool>,
+    pub preprocess: Option<PreprocessOption>,
+}
+
+/// What a search command checks before it's deferred: that it was given
+/// something to search, and that `backend` or one of its fallbacks is up.
+pub async fn check(backend: Backend, options: &SearchOptions<'_>, command: &Command) -> Res<()> {
+    get_link(options.link, options.attachment, command.handle.locale())?;
+
+    ensure_available(backend, &Config::current()).await?;
+
+    Ok(())
+}
+
+/// Everything a search command does when it runs: remembers the link for
+/// suggestions, then [`find`]s it on `backend`.
+pub async fn run(backend: Backend, options: SearchOptions<'_>, command: Command) -> Res<()> {
+    let link = get_link(options.link, options.attachment, command.handle.locale())?;
+
+    let user = command.author_id();
+
+    if let Some(user) = user {
+        recent::searched(user, &link);
+    }
+
+    find(
+        command.handle,
+        backend,
+        user,
+        &link,
+        options.ephemeral,
+        options.preprocess,
+    )
+    .await
+}
+
+/// `link` made safe to show: only web links, without credentials or a
+/// fragment, and with nothing that could close a spoiler early.
+pub fn sanitize(link: &str) -> Option<String> {
+    let mut url = Url::parse(link).ok()?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    url.set_username("").ok()?;
+    url.set_password(None).ok()?;
+    url.set_fragment(None);
+
+    Some(url.as_str().replace('|', "%7C"))
+}
+
+fn spoiler(text: String, nsfw: bool) -> String {
+    if nsfw { text } else { format!("||{text}||") }
+}
+
+/// Whether `link` is a Discord attachment, which stops working once its
+/// signature expires.
+fn is_attachment(link: &str) -> bool {
+    Url::parse(link).is_ok_and(|url| {
+        matches!(
+            url.host_str(),
+            Some("cdn.discordapp.com" | "media.discordapp.net")
+        ) && (url.path().starts_with("/attachments/")
+            || url.path().starts_with("/ephemeral-attachments/"))
+    })
+}
+
+/// Whether `link` is a Discord attachment that can't be downloaded anymore,
+/// going by the expiry Discord signs it with. Ones without one count as
+/// expired, as Discord won't serve them unsigned.
+pub fn expired(link: &str) -> bool {
+    if !is_attachment(link) {
+        return false;
+    }
+
+    let expires = Url::parse(link).ok().and_then(|url| {
+        url.query_pairs()
+            .find(|(key, _)| key == "ex")
+            .and_then(|(_, ex)| u64::from_str_radix(&ex, 16).ok())
+    });
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+
+    expires.is_none_or(|expires| expires <= now)
+}
+
+/// Downloads `link` to attach it to the results again, spoilered unless
+/// `nsfw`. Only done for Discord attachments, as other links don't expire.
+async fn reattach(link: &str, nsfw: bool) -> Option<Upload> {
+    if !is_attachment(link) {
+        return None;
+    }
+
+    let bytes = match frames::download(link).await {
+        Ok(bytes) => bytes?,
+        Err(e) => {
+            warn!(?e, "Unable to download {link} to attach it again");
+
+            return None;
+        }
+    };
+
+    let extension = Url::parse(link)
+        .ok()
+        .and_then(|url| {
+            url.path()
+                .rsplit_once('.')
+                .map(|(_, extension)| extension.to_lowercase())
+        })
+        .filter(|extension| {
+            !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .unwrap_or_else(|| "png".to_owned());
+
+    let name = spoiler_name(format!("original.{extension}"), nsfw);
+
+    Some(Upload::from_bytes(name, bytes, 0))
+}
+
+fn spoiler_name(name: String, nsfw: bool) -> String {
+    if nsfw {
+        name
+    } else {
+        format!("SPOILER_{name}")
+    }
+}
+
+pub(crate) async fn respond(
+    handle: Handle,
+    res: Result<(Backend, Output), SearchError>,
+    cfg: Config,
+    ephemeral: Option<bool>,
+) -> Res<()> {
+    let (backend, result) = res?;
+    let locale = handle.locale();
+    let nsfw = handle.nsfw();
+    let original = sanitize(&result.original_url);
+
+    let mut embed = EmbedBuilder::new()
+        .title(i18n::text(locale, "results-title", &[]))
+        .color(cfg.branding().embed_color())
+        .field(EmbedField {
+            name: i18n::text(locale, "results-original", &[]),
+            value: original.as_ref().map_or_else(
+                || i18n::text(locale, "results-original-hidden", &[]),
+                |link| spoiler(format!("<{link}>"), nsfw),
+            ),
+            inline: false,
+        });
+
+    // Embed thumbnails can't be spoilered, so outside of age-restricted
+    // channels the image is only shown as a spoilered attachment.
+    let mut attachments = Vec::new();
+    if let Some(link) = &original {
+        match reattach(link, nsfw).await {
+            Some(upload) => {
+                if nsfw && let Ok(thumbnail) = ImageSource::attachment(&upload.filename) {
+                    embed = embed.thumbnail(thumbnail);
+                }
+
+                attachments.push(upload);
+            }
+            None if nsfw => {
+                if let Ok(thumbnail) = ImageSource::url(link) {
+                    embed = embed.thumbnail(thumbnail);
+                }
+            }
+            None => {}
+        }
+    }
+
+    if result.items.is_empty() {
+        embed = embed.field(EmbedField {
+            name: i18n::text(locale, "results-none", &[]),
+            value: i18n::text(locale, "results-none-description", &[]),
+            inline: false,
+        });
+    } else {
+        let mut items = result.items;
+
+        items.sort_unstable_by_key(|c| i32::from_f32(c.similarity * 100f32));
+
+        for x in items.iter().rev().take(cfg.settings().top_links() as usize) {
+            embed = embed.field(EmbedField {
+                name: i18n::text(
+                    locale,
+                    "results-similarity",
+                    &[("similarity", format!("{:0.2}", x.similarity).into())],
+                ),
+                value: format!("**<{}>**", x.link),
+                inline: false,
+            });
+        }
+    }
+
+    let embed = embed
+        .footer(EmbedFooterBuilder::new(i18n::text(
+            locale,
+            "results-backend",
+            &[("backend", backend.name().into())],
+        )))
+        .build();
+
+    let mut reply = quiet_reply().embed(embed);
+    reply.attachments = attachments;
+
+    if handle.ephemeral(ephemeral) {
+        reply = reply.ephemeral();
+    }
 
     handle.reply(reply).await?;
 
     Ok(())
 }
 
-pub async fn get_link(
-    handle: &Handle,
+/// What every search command declares: a visible deferral, and a short
+/// per-user cooldown so one person can't eat through the backends' limits.
+pub const fn metadata() -> Metadata {
+    Metadata::new().defer().cooldown(Duration::from_secs(5))
+}
+
+pub fn get_link(
     link: Option<&String>,
     attachment: Option<&Attachment>,
+    locale: &str,
 ) -> Res<String> {
     if let Some(link) = link {
-        get_link_from_link(handle, link.clone()).await
+        get_link_from_link(link.clone(), locale)
     } else if let Some(attachment) = attachment {
-        get_link_from_attachment(handle, attachment.clone()).await
+        get_link_from_attachment(attachment.clone(), locale)
     } else {
-        Err(eyre!("fucked up"))
+        Err(Friendly::new(i18n::text(locale, "no-image", &[])).into())
     }
 }
@@ -0,0 +1,358 @@
+//! Runs the bot's shards, restarting any that close for good, and moving to a
+//! new set of them whenever Discord recommends a different number.
+//!
+//! Each set of shards is a generation. A new generation connects alongside
+//! the old one, which keeps handling events until every new shard is ready,
+//! so resharding doesn't drop any interactions.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::pending,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use sparkle_convenience::Bot;
+use tokio::{
+    sync::{Notify, mpsc},
+    time::{sleep, timeout},
+};
+use tracing::{error, info, warn};
+use twilight_gateway::{
+    CloseFrame, Command, Config as ShardConfig, ConfigBuilder, Event, MessageSender, Shard,
+    ShardId, StreamExt as _,
+};
+use twilight_model::gateway::CloseCode;
+
+use crate::{Res, commands, config::Config, events, presence, recent};
+
+/// The longest a restarting shard waits before connecting again.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// How long a new generation gets to become ready before it's given up on.
+const READY_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Woken when a shard is closed for needing more shards.
+static RESHARD: Notify = Notify::const_new();
+
+/// The generation whose events are handled.
+static ACTIVE: Mutex<Option<Arc<State>>> = Mutex::new(None);
+
+/// What the shards of one generation share.
+#[derive(Default)]
+struct State {
+    /// Whether the generation's events are handled. Only one is at a time.
+    active: AtomicBool,
+    /// Whether the generation has been replaced, and its shards should stop.
+    stopped: AtomicBool,
+    /// How to reach each shard, by number, as of its last (re)start.
+    senders: Mutex<BTreeMap<u32, MessageSender>>,
+}
+
+struct Generation {
+    total: u32,
+    state: Arc<State>,
+}
+
+impl Generation {
+    /// Connects `total` shards, which report their number to `ready` once
+    /// they're ready.
+    fn start(
+        bot: &Arc<Bot>,
+        config: &ShardConfig,
+        total: u32,
+        ready: &mpsc::UnboundedSender<u32>,
+    ) -> Self {
+        let state = Arc::new(State::default());
+
+        for number in 0..total {
+            tokio::spawn(supervise(
+                bot.clone(),
+                config.clone(),
+                ShardId::new(number, total),
+                state.clone(),
+                ready.clone(),
+            ));
+        }
+
+        Self { total, state }
+    }
+
+    fn activate(&self) {
+        self.state.active.store(true, Ordering::Release);
+
+        *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = Some(self.state.clone());
+    }
+
+    /// Closes every shard of the generation for good.
+    fn stop(&self) {
+        self.state.active.store(false, Ordering::Release);
+        self.state.stopped.store(true, Ordering::Release);
+
+        for sender in self
+            .state
+            .senders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+        {
+            let _ = sender.close(CloseFrame::NORMAL);
+        }
+    }
+}
+
+/// Runs as many shards as Discord recommends, forever.
+pub async fn run(bot: Arc<Bot>) -> Res<()> {
+    let config = shard_config(&bot);
+    let (ready, _) = mpsc::unbounded_channel();
+
+    // Work out the first presence before any shard connects with it.
+    presence::advance(&bot).await;
+
+    let rotating = bot.clone();
+    tokio::spawn(async move { presence::rotate(&rotating).await });
+
+    let mut generation = Generation::start(&bot, &config, recommended(&bot).await?, &ready);
+    generation.activate();
+
+    info!("Started {} shards", generation.total);
+
+    loop {
+        let interval = Config::current().settings().connection().reshard_interval();
+        let scheduled = async {
+            match interval {
+                Some(interval) => sleep(interval).await,
+                None => pending().await,
+            }
+        };
+
+        tokio::select! {
+            () = RESHARD.notified() => {}
+            () = scheduled => {}
+        }
+
+        let total = match recommended(&bot).await {
+            Ok(total) => total,
+            Err(e) => {
+                warn!(?e, "Unable to get the recommended number of shards");
+
+                continue;
+            }
+        };
+
+        if total == generation.total {
+            continue;
+        }
+
+        info!("Resharding from {} to {total} shards", generation.total);
+
+        match reshard(&bot, &config, total).await {
+            Some(next) => {
+                // Activated first, so there's always a generation handling
+                // events.
+                next.activate();
+                generation.stop();
+                commands::status::forget_shard_latency().await;
+
+                generation = next;
+
+                info!("Resharded to {total} shards");
+            }
+            None => error!("Gave up on resharding to {total} shards, keeping the current ones"),
+        }
+    }
+}
+
+/// Starts a generation of `total` shards and waits for all of them to be
+/// ready, stopping it if they take too long.
+async fn reshard(bot: &Arc<Bot>, config: &ShardConfig, total: u32) -> Option<Generation> {
+    let (ready, mut ready_rx) = mpsc::unbounded_channel();
+    let generation = Generation::start(bot, config, total, &ready);
+
+    let all_ready = timeout(READY_TIMEOUT, async {
+        let mut waiting: HashSet<u32> = (0..total).collect();
+
+        while !waiting.is_empty() {
+            let Some(number) = ready_rx.recv().await else {
+                return;
+            };
+
+            waiting.remove(&number);
+        }
+    })
+    .await;
+
+    if all_ready.is_ok() {
+        Some(generation)
+    } else {
+        generation.stop();
+
+        None
+    }
+}
+
+async fn recommended(bot: &Bot) -> Res<u32> {
+    Ok(bot.http.gateway().authed().await?.model().await?.shards)
+}
+
+/// What every shard connects with.
+fn shard_config(bot: &Bot) -> ShardConfig {
+    let cfg = Config::current();
+
+    ConfigBuilder::new(cfg.credentials().token().clone(), bot.intents).build()
+}
+
+/// Sends `command` to every shard whose events are being handled.
+pub fn command(command: &impl Command) {
+    let Some(state) = ACTIVE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+    else {
+        return;
+    };
+
+    for (number, sender) in state
+        .senders
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        if let Err(e) = sender.command(command) {
+            warn!(?e, "Unable to send a command to shard {number}");
+        }
+    }
+}
+
+/// Runs shard `id` until its generation is stopped, starting it again if it
+/// closes for good or panics.
+async fn supervise(
+    bot: Arc<Bot>,
+    config: ShardConfig,
+    id: ShardId,
+    state: Arc<State>,
+    ready: mpsc::UnboundedSender<u32>,
+) {
+    let mut failures = 0;
+
+    while !state.stopped.load(Ordering::Acquire) {
+        // Connect with whatever the presence is by now.
+        let mut builder = ConfigBuilder::from(config.clone());
+        if let Some(presence) = presence::current() {
+            builder = builder.presence(presence);
+        }
+
+        let shard = Shard::with_config(id, builder.build());
+
+        state
+            .senders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id.number(), shard.sender());
+
+        let started = Instant::now();
+        let res = tokio::spawn(runner(bot.clone(), shard, state.clone(), ready.clone())).await;
+
+        if state.stopped.load(Ordering::Acquire) {
+            return;
+        }
+
+        if let Err(e) = res {
+            error!(?e, "Shard {id} panicked");
+        }
+
+        // A shard that ran for a while before closing gets a fresh start.
+        failures = if started.elapsed() > MAX_BACKOFF {
+            0
+        } else {
+            failures + 1
+        };
+
+        let backoff = Duration::from_secs(5)
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(MAX_BACKOFF);
+
+        warn!("Shard {id} closed for good, restarting in {backoff:?}");
+
+        sleep(backoff).await;
+    }
+}
+
+async fn handle_event(event: Event, bot: Arc<Bot>) -> Res<()> {
+    let res = match event {
+        Event::InteractionCreate(interaction) => events::interaction_create(bot, interaction).await,
+        Event::MessageCreate(message) => {
+            recent::posted(&message);
+
+            Ok(())
+        }
+
+        _ => Ok(()),
+    };
+
+    if let Err(e) = res {
+        error!("Error handling event: {e}");
+    }
+
+    Ok(())
+}
+
+async fn runner(
+    bot: Arc<Bot>,
+    mut shard: Shard,
+    state: Arc<State>,
+    ready: mpsc::UnboundedSender<u32>,
+) {
+    while let Some(event) = shard.next_event(bot.event_type_flags).await {
+        if state.stopped.load(Ordering::Acquire) {
+            break;
+        }
+
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => {
+                warn!(?error, "Error receiving event");
+
+                continue;
+            }
+        };
+
+        let active = state.active.load(Ordering::Acquire);
+
+        match event {
+            Event::Ready(event) => {
+                info!(
+                    "Shard {} ready, logged in as {}",
+                    shard.id(),
+                    event.user.name
+                );
+
+                let _ = ready.send(shard.id().number());
+            }
+            Event::GatewayClose(Some(frame))
+                if matches!(
+                    CloseCode::try_from(frame.code),
+                    Ok(CloseCode::ShardingRequired)
+                ) =>
+            {
+                warn!("Shard {} was closed for needing more shards", shard.id());
+
+                RESHARD.notify_one();
+            }
+            Event::GatewayHeartbeatAck if active => {
+                commands::status::record_shard_latency(
+                    shard.id().number(),
+                    shard.latency().average(),
+                )
+                .await;
+            }
+            _ if active => {
+                tokio::spawn(handle_event(event, bot.clone()));
+            }
+            _ => {}
+        }
+    }
+}
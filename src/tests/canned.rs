@@ -0,0 +1,147 @@
+//! Backend answers kept as JSON in `canned/`, and served through the stubs.
+//!
+//! Run the tests with `SAUCE_BOT_RECORD=1` to search the real backends
+//! instead, and overwrite the answers with what they returned. That needs
+//! network access and real API keys, in the `config.toml` at
+//! `SAUCE_BOT_RECORD_CONFIG`, or at the root of the repository otherwise.
+//! Recorded answers say when they were recorded; ones without `recorded_at`
+//! were written by hand, and are due for recording.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sauce_api::source::{Item, Output};
+use serde::{Deserialize, Serialize};
+
+use super::harness::stub_result;
+use crate::{backend::Backend, config::Config};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Canned {
+    backend: Backend,
+    link: String,
+    /// Seconds since the Unix epoch, unless written by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recorded_at: Option<u64>,
+    #[serde(flatten)]
+    answer: Answer,
+}
+
+/// What `sauce-api` returned, an error being kept as its message.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Answer {
+    Output {
+        original_url: String,
+        items: Vec<CannedItem>,
+    },
+    Error(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CannedItem {
+    link: String,
+    similarity: f32,
+}
+
+impl From<Result<Output, String>> for Answer {
+    fn from(result: Result<Output, String>) -> Self {
+        match result {
+            Ok(output) => Self::Output {
+                original_url: output.original_url,
+                items: output
+                    .items
+                    .into_iter()
+                    .map(|item| CannedItem {
+                        link: item.link,
+                        similarity: item.similarity,
+                    })
+                    .collect(),
+            },
+            Err(message) => Self::Error(message),
+        }
+    }
+}
+
+impl From<Answer> for Result<Output, String> {
+    fn from(answer: Answer) -> Self {
+        match answer {
+            Answer::Output {
+                original_url,
+                items,
+            } => Ok(Output {
+                original_url,
+                items: items
+                    .into_iter()
+                    .map(|item| Item {
+                        link: item.link,
+                        similarity: item.similarity,
+                    })
+                    .collect(),
+            }),
+            Answer::Error(message) => Err(message),
+        }
+    }
+}
+
+fn path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/tests/canned")
+        .join(format!("{name}.json"))
+}
+
+/// Makes `backend` answer `link` with the canned answer `name`, recording it
+/// first if `SAUCE_BOT_RECORD` is set.
+pub async fn use_canned(name: &str, backend: Backend, link: &str) {
+    let canned = if env::var_os("SAUCE_BOT_RECORD").is_some() {
+        record(name, backend, link).await
+    } else {
+        let content = fs::read_to_string(path(name)).expect("canned answer exists");
+
+        serde_json::from_str::<Canned>(&content).expect("valid canned answer")
+    };
+
+    assert_eq!(
+        canned.backend, backend,
+        "canned answer {name} is for another backend"
+    );
+    assert_eq!(
+        canned.link, link,
+        "canned answer {name} is for another link"
+    );
+
+    stub_result(backend, link, canned.answer.into());
+}
+
+/// Searches the real `backend` for `link`, and keeps the answer as `name`.
+/// Goes around the stubs, and the config the tests use.
+async fn record(name: &str, backend: Backend, link: &str) -> Canned {
+    let config = env::var_os("SAUCE_BOT_RECORD_CONFIG").map_or_else(
+        || Path::new(env!("CARGO_MANIFEST_DIR")).join("config.toml"),
+        PathBuf::from,
+    );
+    let cfg = Config::read_from(&config).expect("a config with real API keys to record with");
+
+    let result = match backend.searcher(&cfg).await {
+        Ok(searcher) => searcher.search(link).await,
+        Err(e) => Err(e),
+    };
+
+    let canned = Canned {
+        backend,
+        link: link.to_owned(),
+        recorded_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|now| now.as_secs()),
+        answer: result.map_err(|e| e.to_string()).into(),
+    };
+
+    let content = serde_json::to_string_pretty(&canned).expect("serializable canned answer");
+    fs::write(path(name), content + "\n").expect("able to write the canned answer");
+
+    canned
+}
@@ -0,0 +1,713 @@
+use serde_json::{Value, json};
+
+use twilight_model::{
+    application::interaction::InteractionContextType,
+    id::{AnonymizableId, Id},
+};
+
+use super::harness::{APPLICATION_ID, GUILD_ID, Harness, OWNER_ID, Request, stub, stub_result};
+use crate::{backend::Backend, config::Config, recent, sauce_finder};
+
+const DEFERRED: u64 = 5;
+const CHANNEL_MESSAGE: u64 = 4;
+const AUTOCOMPLETE_RESULT: u64 = 8;
+const EPHEMERAL: u64 = 1 << 6;
+
+fn link_option(link: &str) -> Value {
+    json!([{ "name": "link", "type": 3, "value": link }])
+}
+
+fn assert_callback(request: &Request, kind: u64) -> &Value {
+    assert_eq!(request.method, "POST");
+    assert!(
+        request.path.starts_with("/interactions/") && request.path.ends_with("/callback"),
+        "unexpected path {}",
+        request.path
+    );
+    assert_eq!(request.body["type"], kind);
+
+    &request.body["data"]
+}
+
+fn assert_followup(request: &Request) -> &Value {
+    assert_eq!(request.method, "POST");
+    assert!(
+        request.path.starts_with("/webhooks/2000/token-"),
+        "unexpected path {}",
+        request.path
+    );
+
+    &request.body
+}
+
+fn assert_original_edit(request: &Request) -> &Value {
+    assert_eq!(request.method, "PATCH");
+    assert!(
+        request.path.starts_with("/webhooks/2000/token-")
+            && request.path.ends_with("/messages/@original"),
+        "unexpected path {}",
+        request.path
+    );
+
+    &request.body
+}
+
+fn field<'a>(embed: &'a Value, name: &str) -> &'a Value {
+    embed["fields"]
+        .as_array()
+        .and_then(|fields| fields.iter().find(|f| f["name"] == name))
+        .map_or(&Value::Null, |f| &f["value"])
+}
+
+#[tokio::test]
+async fn saucenao_defers_then_follows_up_with_results() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/saucenao-results.png";
+    stub(
+        Backend::SauceNao,
+        link,
+        &[
+            ("https://example.com/low", 40.0),
+            ("https://example.com/high", 93.5),
+        ],
+    );
+
+    let requests = harness.run("saucenao", link_option(link)).await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert!(assert_callback(&requests[0], DEFERRED)["flags"].is_null());
+
+    let embed = &assert_followup(&requests[1])["embeds"][0];
+    assert_eq!(embed["title"], "Results");
+    assert_eq!(*field(embed, "Original Link"), format!("||<{link}>||"));
+    assert_eq!(embed["fields"][1]["name"], "Similarity: 93.50");
+    assert_eq!(
+        embed["fields"][1]["value"],
+        "**<https://example.com/high>**"
+    );
+    assert_eq!(embed["fields"][2]["name"], "Similarity: 40.00");
+    assert_eq!(embed["footer"]["text"], "Searched with SauceNao");
+}
+
+#[tokio::test]
+async fn fuzzysearch_defers_ephemerally_when_asked() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/fuzzysearch-ephemeral.png";
+    stub(
+        Backend::FuzzySearch,
+        link,
+        &[("https://example.com/a", 80.0)],
+    );
+
+    let requests = harness
+        .run(
+            "fuzzysearch",
+            json!([
+                { "name": "link", "type": 3, "value": link },
+                { "name": "ephemeral", "type": 5, "value": true },
+            ]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert_eq!(assert_callback(&requests[0], DEFERRED)["flags"], EPHEMERAL);
+    assert_eq!(assert_followup(&requests[1])["flags"], EPHEMERAL);
+}
+
+#[tokio::test]
+async fn searching_where_only_the_user_installed_the_bot_is_hidden() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/iqdb-user-installed.png";
+    stub(Backend::Iqdb, link, &[("https://example.com/a", 90.0)]);
+
+    let requests = harness.run_user_installed("iqdb", link_option(link)).await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert_eq!(assert_callback(&requests[0], DEFERRED)["flags"], EPHEMERAL);
+    assert_eq!(assert_followup(&requests[1])["flags"], EPHEMERAL);
+
+    let requests = harness
+        .run_user_installed(
+            "iqdb",
+            json!([
+                { "name": "link", "type": 3, "value": link },
+                { "name": "ephemeral", "type": 5, "value": false },
+            ]),
+        )
+        .await;
+
+    assert!(assert_callback(&requests[0], DEFERRED)["flags"].is_null());
+}
+
+#[tokio::test]
+async fn searching_in_a_server_the_bot_was_added_to_is_visible() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/iqdb-guild-installed.png";
+    stub(Backend::Iqdb, link, &[]);
+
+    let requests = harness
+        .run_with("iqdb", link_option(link), |interaction| {
+            interaction.context = Some(InteractionContextType::Guild);
+            interaction.guild_id = Some(Id::new(GUILD_ID));
+            interaction.authorizing_integration_owners.guild =
+                Some(AnonymizableId::Id(Id::new(GUILD_ID)));
+        })
+        .await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert!(assert_callback(&requests[0], DEFERRED)["flags"].is_null());
+}
+
+#[tokio::test]
+async fn iqdb_reports_zero_results() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/iqdb-nothing.png";
+    stub(Backend::Iqdb, link, &[]);
+
+    let requests = harness.run("iqdb", link_option(link)).await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert_callback(&requests[0], DEFERRED);
+
+    let embed = &assert_followup(&requests[1])["embeds"][0];
+    assert_eq!(
+        field(embed, "Found zero results"),
+        "Unable to find any results for the given link."
+    );
+}
+
+#[tokio::test]
+async fn search_without_an_image_fails_before_deferring() {
+    let harness = Harness::new().await;
+
+    let requests = harness.run("fuzzysearch", json!([])).await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert_eq!(data["flags"], EPHEMERAL);
+
+    let content = data["content"].as_str().unwrap_or_default();
+    assert!(content.starts_with("No image was provided, whether by link or attachment."));
+    assert!(content.contains("Error ID: `"));
+}
+
+#[tokio::test]
+async fn search_with_an_invalid_link_fails_before_deferring() {
+    let harness = Harness::new().await;
+
+    let requests = harness.run("iqdb", link_option("not a link")).await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("Invalid link provided"))
+    );
+}
+
+#[tokio::test]
+async fn searching_again_too_quickly_is_refused_before_deferring() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/iqdb-cooldown.png";
+    let user = 6001;
+    stub(Backend::Iqdb, link, &[]);
+
+    let requests = harness.run_as(user, "iqdb", link_option(link)).await;
+    assert_callback(&requests[0], DEFERRED);
+
+    let requests = harness.run_as(user, "iqdb", link_option(link)).await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("You're using `/iqdb` too quickly."))
+    );
+}
+
+#[tokio::test]
+async fn link_autocomplete_suggests_recent_searches() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/autocomplete-recent.png";
+    let user = 6002;
+    stub(Backend::FuzzySearch, link, &[]);
+
+    harness.run_as(user, "fuzzysearch", link_option(link)).await;
+
+    let requests = harness
+        .autocomplete_as(
+            user,
+            "saucenao",
+            json!([{ "name": "link", "type": 3, "value": "autocomplete", "focused": true }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], AUTOCOMPLETE_RESULT);
+    assert_eq!(data["choices"], json!([{ "name": link, "value": link }]));
+}
+
+#[tokio::test]
+async fn batch_follows_up_once_per_image() {
+    let harness = Harness::new().await;
+    let first = "https://example.com/batch-first.png";
+    let second = "https://example.com/batch-second.png";
+    stub(
+        Backend::Iqdb,
+        first,
+        &[("https://example.com/batch-a", 70.0)],
+    );
+    stub(Backend::Iqdb, second, &[]);
+
+    let requests = harness
+        .run(
+            "batch",
+            json!([
+                { "name": "backend", "type": 3, "value": "iqdb" },
+                { "name": "links", "type": 3, "value": format!("{first}\n {second}") },
+            ]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 3, "{requests:#?}");
+    assert_callback(&requests[0], DEFERRED);
+    assert_eq!(
+        *field(&assert_followup(&requests[1])["embeds"][0], "Original Link"),
+        format!("||<{first}>||")
+    );
+    assert_eq!(
+        *field(&assert_followup(&requests[2])["embeds"][0], "Original Link"),
+        format!("||<{second}>||")
+    );
+}
+
+#[tokio::test]
+async fn batch_rejects_too_many_images_before_deferring() {
+    let harness = Harness::new().await;
+    let links = (0..11)
+        .map(|n| format!("https://example.com/batch-{n}.png"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let requests = harness
+        .run(
+            "batch",
+            json!([
+                { "name": "backend", "type": 3, "value": "iqdb" },
+                { "name": "links", "type": 3, "value": links },
+            ]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("That's 11 images"))
+    );
+}
+
+#[tokio::test]
+async fn batch_failures_cannot_ping_through_the_link() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/batch-failing/><@&5000>";
+    stub_result(
+        Backend::Iqdb,
+        link,
+        Err("HTTP status client error (415 Unsupported Media Type)".to_owned()),
+    );
+
+    let requests = harness
+        .run(
+            "batch",
+            json!([
+                { "name": "backend", "type": 3, "value": "iqdb" },
+                { "name": "links", "type": 3, "value": link },
+            ]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    let followup = assert_followup(&requests[1]);
+    assert_eq!(followup["allowed_mentions"], json!({ "parse": [] }));
+
+    let content = followup["content"].as_str().unwrap_or_default();
+    assert!(
+        content.starts_with("Image 1 (<https://example.com/batch-failing/%3E%3C@&5000%3E>)"),
+        "{content}"
+    );
+}
+
+fn history(subcommand: &str) -> Value {
+    json!([{ "name": subcommand, "type": 1, "options": [] }])
+}
+
+#[tokio::test]
+async fn history_keeps_searches_only_once_turned_on_and_forgets_them_on_clear() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/history-kept.png";
+    let user = 6003;
+    stub(Backend::Iqdb, link, &[]);
+    stub(
+        Backend::FuzzySearch,
+        link,
+        &[("https://example.com/history-source", 88.0)],
+    );
+
+    harness.run_as(user, "iqdb", link_option(link)).await;
+
+    let requests = harness.run_as(user, "history", history("list")).await;
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("Your history is off."))
+    );
+
+    harness.run_as(user, "history", history("on")).await;
+    harness.run_as(user, "fuzzysearch", link_option(link)).await;
+
+    let requests = harness.run_as(user, "history", history("list")).await;
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert_eq!(data["flags"], EPHEMERAL);
+
+    let description = data["embeds"][0]["description"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(description.contains(link), "{description}");
+    assert!(
+        description.contains("88.00% <https://example.com/history-source>"),
+        "{description}"
+    );
+    assert_eq!(
+        data["components"][0]["components"][0]["custom_id"],
+        "history:rerun:1"
+    );
+
+    assert!(recent::suggestions(Some(Id::new(user)), None, "").contains(&link.to_owned()));
+
+    harness.run_as(user, "history", history("clear")).await;
+
+    let requests = harness.run_as(user, "history", history("list")).await;
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("Your history is off."))
+    );
+    assert!(recent::suggestions(Some(Id::new(user)), None, "").is_empty());
+}
+
+#[test]
+fn only_expired_discord_attachments_are_stale() {
+    let attachment = "https://cdn.discordapp.com/attachments/1/2/a.png";
+
+    assert!(!sauce_finder::expired("https://example.com/a.png"));
+    assert!(sauce_finder::expired(attachment));
+    assert!(sauce_finder::expired(&format!(
+        "{attachment}?ex=1&is=0&hm=ab"
+    )));
+    assert!(!sauce_finder::expired(&format!(
+        "{attachment}?ex=ffffffffff&is=0&hm=ab"
+    )));
+}
+
+#[tokio::test]
+async fn basic_commands_reply_ephemerally() {
+    let harness = Harness::new().await;
+    let invite =
+        format!("client_id={APPLICATION_ID}&scope=bot%20applications.commands&permissions=0");
+
+    for (name, expected) in [
+        ("issue", "https://github.com/lyssieth/sauce-bot/issues"),
+        ("invite", invite.as_str()),
+    ] {
+        let requests = harness.run(name, json!([])).await;
+
+        assert_eq!(requests.len(), 1, "{name}: {requests:#?}");
+        let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+        assert_eq!(data["flags"], EPHEMERAL);
+        assert!(
+            data["content"]
+                .as_str()
+                .is_some_and(|c| c.contains(expected)),
+            "{name}: {data:#?}"
+        );
+    }
+
+    for (name, title) in [("help", "Help"), ("support", "Support")] {
+        let requests = harness.run(name, json!([])).await;
+
+        assert_eq!(requests.len(), 1, "{name}: {requests:#?}");
+        let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+        assert_eq!(data["flags"], EPHEMERAL);
+        assert_eq!(data["embeds"][0]["title"], title);
+    }
+}
+
+#[tokio::test]
+async fn basic_commands_work_without_a_channel() {
+    let harness = Harness::new().await;
+
+    for name in ["help", "issue", "support", "invite"] {
+        let requests = harness
+            .run_with(name, json!([]), |interaction| interaction.channel = None)
+            .await;
+
+        assert_eq!(requests.len(), 1, "{name}: {requests:#?}");
+        assert_eq!(
+            assert_callback(&requests[0], CHANNEL_MESSAGE)["flags"],
+            EPHEMERAL
+        );
+    }
+}
+
+#[tokio::test]
+async fn help_is_generated_from_the_registry() {
+    let harness = Harness::new().await;
+
+    let requests = harness.run("help", json!([])).await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let embed = &assert_callback(&requests[0], CHANNEL_MESSAGE)["embeds"][0];
+    let commands = field(embed, "Commands").as_str().unwrap_or_default();
+    assert!(commands.contains("- `/iqdb`: "), "{commands}");
+    assert!(!commands.contains("/admin"), "{commands}");
+
+    let backends = field(embed, "Backends").as_str().unwrap_or_default();
+    assert!(backends.contains("searches per 30s left"), "{backends}");
+    assert!(
+        backends.contains("No limits on the bot's side"),
+        "{backends}"
+    );
+}
+
+#[tokio::test]
+async fn help_describes_one_command() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .run(
+            "help",
+            json!([{ "name": "command", "type": 3, "value": "/iqdb" }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let embed = &assert_callback(&requests[0], CHANNEL_MESSAGE)["embeds"][0];
+    assert_eq!(embed["title"], "/iqdb");
+    assert!(
+        field(embed, "Options")
+            .as_str()
+            .is_some_and(|options| options.contains("- `preprocess`: "))
+    );
+    assert!(
+        field(embed, "Usage")
+            .as_str()
+            .is_some_and(|usage| usage.contains("once every 5s"))
+    );
+    assert!(field(embed, "IQDB").is_string(), "{embed:#}");
+
+    let requests = harness
+        .run(
+            "help",
+            json!([{ "name": "command", "type": 3, "value": "nope" }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    assert!(
+        assert_callback(&requests[0], CHANNEL_MESSAGE)["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("There's no `/nope` command."))
+    );
+}
+
+#[tokio::test]
+async fn errors_echoing_the_users_input_cannot_ping() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .run(
+            "help",
+            json!([{ "name": "command", "type": 3, "value": "@everyone" }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.contains("@everyone"))
+    );
+    assert_eq!(data["allowed_mentions"], json!({ "parse": [] }));
+}
+
+#[tokio::test]
+async fn help_autocompletes_command_names() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .autocomplete_as(
+            4242,
+            "help",
+            json!([{ "name": "command", "type": 3, "value": "hi", "focused": true }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], AUTOCOMPLETE_RESULT);
+    assert_eq!(
+        data["choices"],
+        json!([{ "name": "history", "value": "history" }])
+    );
+}
+
+#[tokio::test]
+async fn status_replies_with_an_embed() {
+    let harness = Harness::new().await;
+
+    let requests = harness.run("status", json!([])).await;
+
+    // The number of servers is cached, so only the first run in this process
+    // asks for it.
+    let (reply, fetches) = requests.split_last().expect("a reply");
+    assert!(fetches.len() <= 1, "{requests:#?}");
+    assert!(
+        fetches
+            .iter()
+            .all(|r| r.method == "GET" && r.path == "/applications/@me"),
+        "{requests:#?}"
+    );
+
+    let data = assert_callback(reply, CHANNEL_MESSAGE);
+    assert_eq!(data["flags"], EPHEMERAL);
+
+    let embed = &data["embeds"][0];
+    assert_eq!(embed["title"], "Status");
+    assert_eq!(field(embed, "Servers"), "42");
+    assert_eq!(field(embed, "Shard latency"), "No heartbeats yet");
+
+    let cache = field(embed, "Cache").as_str().unwrap_or_default();
+    assert!(cache.starts_with("- Recent searches: "), "{cache}");
+    assert!(!cache.contains("Posted images"), "{cache}");
+}
+
+#[tokio::test]
+async fn admin_rejects_non_owners() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .run(
+            "admin",
+            json!([{ "name": "errors", "type": 1, "options": [] }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 1, "{requests:#?}");
+    let data = assert_callback(&requests[0], CHANNEL_MESSAGE);
+    assert!(
+        data["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with("Only the bot's owner can use this command."))
+    );
+}
+
+#[tokio::test]
+async fn admin_errors_edits_the_deferred_response() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .run_as(
+            OWNER_ID,
+            "admin",
+            json!([{ "name": "errors", "type": 1, "options": [] }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+    assert_eq!(assert_callback(&requests[0], DEFERRED)["flags"], EPHEMERAL);
+    assert_eq!(
+        assert_original_edit(&requests[1])["embeds"][0]["title"],
+        "Recent errors"
+    );
+}
+
+#[tokio::test]
+async fn admin_presence_fills_in_the_template() {
+    let harness = Harness::new().await;
+
+    let requests = harness
+        .run_as(
+            OWNER_ID,
+            "admin",
+            json!([{
+                "name": "presence",
+                "type": 1,
+                "options": [
+                    { "name": "text", "type": 3, "value": "in {guilds} servers" },
+                    { "name": "activity", "type": 3, "value": "watching" },
+                ],
+            }]),
+        )
+        .await;
+
+    let reply = requests.last().expect("a reply");
+    assert_eq!(
+        assert_original_edit(reply)["content"],
+        "The presence is now `in 42 servers`."
+    );
+
+    let requests = harness
+        .run_as(
+            OWNER_ID,
+            "admin",
+            json!([{ "name": "presence", "type": 1, "options": [] }]),
+        )
+        .await;
+
+    assert_eq!(
+        assert_original_edit(&requests[1])["content"],
+        "Went back to the presence from `config.toml`."
+    );
+}
+
+#[tokio::test]
+async fn failing_searches_fall_back_to_the_next_backend() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/fallback.png";
+    stub_result(
+        Backend::FuzzySearch,
+        link,
+        Err("401 Unauthorized: invalid api key".to_owned()),
+    );
+    stub(Backend::Iqdb, link, &[("https://example.com/found", 75.0)]);
+
+    let handle = harness.handle("fuzzysearch");
+    let cfg = Config::default();
+
+    let (answered, output) = sauce_finder::search(
+        &handle,
+        Backend::FuzzySearch,
+        &[Backend::Iqdb],
+        &cfg,
+        link,
+        None,
+    )
+    .await
+    .expect("IQDB answers instead");
+
+    assert_eq!(answered, Backend::Iqdb);
+    assert_eq!(output.items.len(), 1);
+
+    let error = sauce_finder::search(&handle, Backend::FuzzySearch, &[], &cfg, link, None)
+        .await
+        .expect_err("nothing to fall back on");
+
+    assert_eq!(error.backend(), Backend::FuzzySearch);
+}
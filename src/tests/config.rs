@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use crate::{
+    backend::{self, Backend},
+    config::{ActivityKind, Config, PresenceStatus},
+};
+
+fn with_branding(branding: Option<&str>) -> Config {
+    let mut cfg = toml::Table::try_from(Config::default()).expect("serializable config");
+    cfg.remove("branding");
+
+    if let Some(branding) = branding {
+        cfg.insert(
+            "branding".to_owned(),
+            toml::from_str::<toml::Table>(branding)
+                .expect("valid branding")
+                .into(),
+        );
+    }
+
+    cfg.try_into().expect("valid config")
+}
+
+#[test]
+fn configs_without_branding_keep_the_defaults() {
+    let cfg = with_branding(None);
+
+    assert_eq!(cfg.branding().embed_color(), 0x8B_D8C6);
+    assert_eq!(
+        cfg.branding().issue_tracker(),
+        "https://github.com/lyssieth/sauce-bot/issues"
+    );
+    assert_eq!(cfg.branding().support_links().len(), 2);
+}
+
+#[test]
+fn branding_can_be_replaced() {
+    let cfg = with_branding(Some(
+        r#"
+        embed_color = 0xFF0000
+        issue_tracker = "https://example.com/issues"
+        support_message = ""
+
+        [[support_links]]
+        name = "Ko-fi"
+        description = "Buy us a coffee."
+        url = "https://ko-fi.com/example"
+        "#,
+    ));
+
+    let branding = cfg.branding();
+    assert_eq!(branding.embed_color(), 0xFF_0000);
+    assert_eq!(branding.issue_tracker(), "https://example.com/issues");
+    assert!(branding.support_message().is_empty());
+    assert_eq!(branding.support_links().len(), 1);
+    assert_eq!(branding.support_links()[0].name, "Ko-fi");
+}
+
+#[test]
+fn presence_is_read_from_the_settings() {
+    let mut cfg = toml::Table::try_from(Config::default()).expect("serializable config");
+    let settings = cfg
+        .get_mut("settings")
+        .and_then(toml::Value::as_table_mut)
+        .expect("settings table");
+
+    settings.insert(
+        "presence".to_owned(),
+        toml::from_str::<toml::Table>(
+            r#"
+            status = "dnd"
+            rotate_secs = 5
+
+            [[messages]]
+            activity = "watching"
+            text = "for sauce"
+
+            [[messages]]
+            text = "in {guilds} servers"
+            "#,
+        )
+        .expect("valid presence")
+        .into(),
+    );
+
+    let cfg: Config = cfg.try_into().expect("valid config");
+    let presence = cfg.settings().presence();
+
+    assert_eq!(presence.status(), PresenceStatus::Dnd);
+    assert_eq!(presence.rotate_interval(), Duration::from_secs(15));
+    assert_eq!(presence.messages().len(), 2);
+    assert_eq!(presence.messages()[0].activity, ActivityKind::Watching);
+    assert_eq!(presence.messages()[1].activity, ActivityKind::Playing);
+    assert_eq!(presence.messages()[1].text, "in {guilds} servers");
+}
+
+#[test]
+fn the_queue_is_off_by_default_and_waits_within_the_token_lifetime() {
+    let cfg = Config::default();
+    assert_eq!(cfg.settings().queue().max_length(), 0);
+
+    let mut cfg = toml::Table::try_from(cfg).expect("serializable config");
+    let settings = cfg
+        .get_mut("settings")
+        .and_then(toml::Value::as_table_mut)
+        .expect("settings table");
+
+    settings.insert(
+        "queue".to_owned(),
+        toml::from_str::<toml::Table>("max_length = 10\nmax_wait_secs = 3600")
+            .expect("valid queue")
+            .into(),
+    );
+
+    let cfg: Config = cfg.try_into().expect("valid config");
+    let queue = cfg.settings().queue();
+
+    assert_eq!(queue.max_length(), 10);
+    assert_eq!(queue.max_wait(), Duration::from_secs(12 * 60));
+}
+
+#[test]
+fn fallbacks_skip_the_chosen_backend_and_repeats() {
+    let mut cfg = toml::Table::try_from(Config::default()).expect("serializable config");
+    let settings = cfg
+        .get_mut("settings")
+        .and_then(toml::Value::as_table_mut)
+        .expect("settings table");
+
+    settings.insert(
+        "fallback".to_owned(),
+        toml::Value::try_from(["saucenao", "iqdb", "fuzzysearch", "iqdb"]).expect("valid list"),
+    );
+
+    let cfg: Config = cfg.try_into().expect("valid config");
+
+    assert!(
+        Config::default()
+            .settings()
+            .fallbacks(Backend::SauceNao)
+            .is_empty()
+    );
+    assert_eq!(
+        cfg.settings().fallbacks(Backend::SauceNao),
+        [Backend::Iqdb, Backend::FuzzySearch]
+    );
+    assert_eq!(
+        cfg.settings().fallbacks(Backend::Iqdb),
+        [Backend::SauceNao, Backend::FuzzySearch]
+    );
+}
+
+#[test]
+fn health_checks_cannot_probe_in_a_tight_loop() {
+    let mut cfg = toml::Table::try_from(Config::default()).expect("serializable config");
+    let settings = cfg
+        .get_mut("settings")
+        .and_then(toml::Value::as_table_mut)
+        .expect("settings table");
+
+    settings.insert(
+        "health_check".to_owned(),
+        toml::from_str::<toml::Table>(
+            "interval_secs = 0\nretry_secs = 0\nfailures_before_down = 0",
+        )
+        .expect("valid health check")
+        .into(),
+    );
+
+    let cfg: Config = cfg.try_into().expect("valid config");
+    let health_check = cfg.settings().health_check();
+
+    assert_eq!(health_check.interval(), Duration::from_secs(60));
+    assert_eq!(health_check.retry(), Duration::from_secs(10));
+    assert_eq!(health_check.failures_before_down(), 1);
+}
+
+#[test]
+fn retry_backoff_is_capped() {
+    let mut cfg = toml::Table::try_from(Config::default()).expect("serializable config");
+    let settings = cfg
+        .get_mut("settings")
+        .and_then(toml::Value::as_table_mut)
+        .expect("settings table");
+
+    settings.insert(
+        "iqdb".to_owned(),
+        toml::from_str::<toml::Table>(&format!("backoff_ms = {}\nretries = 255", i64::MAX))
+            .expect("valid backend settings")
+            .into(),
+    );
+
+    let cfg: Config = cfg.try_into().expect("valid config");
+    let settings = cfg.settings().backend(Backend::Iqdb);
+
+    for attempt in [0, 1, 8, 40, 255] {
+        assert!(backend::backoff(settings, attempt) <= Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::StatusCode;
+use ring::signature::{Ed25519KeyPair, KeyPair as _};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+use super::harness::{APPLICATION_ID, Harness};
+use crate::endpoint;
+
+/// Seconds since the epoch, `ago` in the past.
+fn timestamp(ago: Duration) -> String {
+    (SystemTime::now() - ago)
+        .duration_since(UNIX_EPOCH)
+        .expect("after the epoch")
+        .as_secs()
+        .to_string()
+}
+
+struct Endpoint {
+    url: String,
+    key: Ed25519KeyPair,
+    client: reqwest::Client,
+}
+
+impl Endpoint {
+    /// Serves the endpoint on a free port, for the harness's bot.
+    async fn start() -> Self {
+        let harness = Harness::new().await;
+        let key = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).expect("valid seed");
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("free port");
+        let url = format!("http://{}", listener.local_addr().expect("bound"));
+
+        tokio::spawn(endpoint::run(
+            harness.bot.clone(),
+            listener,
+            key.public_key().as_ref().to_vec(),
+        ));
+
+        Self {
+            url,
+            key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post(
+        &self,
+        timestamp: &str,
+        body: &Value,
+        signature: Option<String>,
+    ) -> reqwest::Response {
+        let body = body.to_string();
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("x-signature-timestamp", timestamp)
+            .body(body);
+
+        if let Some(signature) = signature {
+            request = request.header("x-signature-ed25519", signature);
+        }
+
+        request.send().await.expect("endpoint reachable")
+    }
+
+    fn sign(&self, timestamp: &str, body: &Value) -> String {
+        let message = format!("{timestamp}{body}");
+
+        hex::encode(self.key.sign(message.as_bytes()))
+    }
+}
+
+fn ping() -> Value {
+    json!({
+        "id": "10",
+        "application_id": APPLICATION_ID.to_string(),
+        "type": 1,
+        "token": "token-ping",
+        "entitlements": [],
+        "authorizing_integration_owners": {},
+        "version": 1,
+    })
+}
+
+#[tokio::test]
+async fn signed_pings_are_answered_with_a_pong() {
+    let endpoint = Endpoint::start().await;
+    let body = ping();
+    let now = timestamp(Duration::ZERO);
+
+    let res = endpoint
+        .post(&now, &body, Some(endpoint.sign(&now, &body)))
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.bytes().await.expect("response body");
+    assert_eq!(
+        serde_json::from_slice::<Value>(&body).expect("json response"),
+        json!({ "type": 1 })
+    );
+}
+
+#[tokio::test]
+async fn unsigned_or_tampered_interactions_are_refused() {
+    let endpoint = Endpoint::start().await;
+    let body = ping();
+    let now = timestamp(Duration::ZERO);
+
+    let res = endpoint.post(&now, &body, None).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let signature = endpoint.sign(&now, &body);
+    let mut tampered = body;
+    tampered["token"] = json!("token-tampered");
+
+    let res = endpoint.post(&now, &tampered, Some(signature)).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn replayed_interactions_are_refused() {
+    let endpoint = Endpoint::start().await;
+    let body = ping();
+    let stale = timestamp(Duration::from_secs(10 * 60));
+
+    let res = endpoint
+        .post(&stale, &body, Some(endpoint.sign(&stale, &body)))
+        .await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn only_timestamps_close_to_now_are_fresh() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    assert!(endpoint::fresh("1700000000", now));
+    assert!(endpoint::fresh("1699999800", now));
+    assert!(endpoint::fresh("1700000300", now));
+    assert!(!endpoint::fresh("1699999000", now));
+    assert!(!endpoint::fresh("1700001000", now));
+    assert!(!endpoint::fresh("not a timestamp", now));
+}
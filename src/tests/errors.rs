@@ -0,0 +1,43 @@
+use crate::error::BackendFailure;
+
+fn classify(message: &str) -> BackendFailure {
+    BackendFailure::classify(&*crate::backend::SourceError::from(message))
+}
+
+#[test]
+fn http_failures_are_classified_by_their_status() {
+    assert_eq!(
+        classify(
+            "HTTP status client error (401 Unauthorized) for url (https://api-next.fuzzysearch.net/v1/url?url=x)"
+        ),
+        BackendFailure::BadKey
+    );
+    assert_eq!(
+        classify(
+            "HTTP status server error (503 Service Unavailable) for url (https://saucenao.com/search.php)"
+        ),
+        BackendFailure::Down
+    );
+    assert_eq!(
+        classify(
+            "HTTP status client error (429 Too Many Requests) for url (https://saucenao.com/search.php)"
+        ),
+        BackendFailure::RateLimited
+    );
+}
+
+#[test]
+fn the_users_link_does_not_decide_the_classification() {
+    assert_eq!(
+        classify(
+            "Unexpected response for https://cdn.discordapp.com/attachments/401500/429413/unauthorized-timeout.png"
+        ),
+        BackendFailure::Unknown
+    );
+    assert_eq!(
+        classify(
+            "HTTP status client error (404 Not Found) for url (https://example.com/503/api_key.png)"
+        ),
+        BackendFailure::Unknown
+    );
+}
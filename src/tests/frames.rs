@@ -0,0 +1,241 @@
+use std::{
+    io::Cursor,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use image::{Frame, ImageFormat, Rgba, RgbaImage, codecs::gif::GifEncoder};
+use sauce_api::source::{Item, Output};
+use serde_json::json;
+
+use super::harness::{Harness, Request, stub_download};
+use crate::frames::{self, Fetcher as _, HttpFetcher, is_public, key_frames, merge};
+
+fn gif(frames: u8) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        encoder
+            .encode_frames((0..frames).map(|n| {
+                Frame::new(RgbaImage::from_pixel(
+                    4,
+                    4,
+                    Rgba([n.wrapping_mul(20), 0, 0, 255]),
+                ))
+            }))
+            .expect("encodable frames");
+    }
+
+    bytes
+}
+
+fn png() -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]))
+        .write_to(&mut bytes, ImageFormat::Png)
+        .expect("encodable png");
+
+    bytes.into_inner()
+}
+
+#[test]
+fn key_frames_are_spread_across_the_animation() {
+    let frames = key_frames(&gif(10), 3)
+        .expect("decodable gif")
+        .expect("animated gif");
+
+    assert_eq!(frames.len(), 3);
+
+    let reds: Vec<u8> = frames
+        .iter()
+        .map(|png| {
+            image::load_from_memory(png)
+                .expect("valid png")
+                .to_rgba8()
+                .get_pixel(0, 0)[0]
+        })
+        .collect();
+    // Frames 0, 4 and 9, give or take the GIF palette.
+    assert!(reds[0] < 10, "{reds:?}");
+    assert!(reds[1].abs_diff(80) < 10, "{reds:?}");
+    assert!(reds[2].abs_diff(180) < 10, "{reds:?}");
+}
+
+#[test]
+fn long_animations_still_keep_their_first_and_last_frames() {
+    let frames = key_frames(&gif(100), 3)
+        .expect("decodable gif")
+        .expect("animated gif");
+
+    assert_eq!(frames.len(), 3);
+
+    let red = |png: &[u8]| {
+        image::load_from_memory(png)
+            .expect("valid png")
+            .to_rgba8()
+            .get_pixel(0, 0)[0]
+    };
+    // Frame 99 is 99 * 20 % 256 red.
+    assert!(red(&frames[0]) < 10);
+    assert!(red(&frames[2]).abs_diff(188) < 10);
+}
+
+#[test]
+fn single_frame_images_are_left_alone() {
+    assert!(key_frames(&gif(1), 3).expect("decodable gif").is_none());
+}
+
+#[test]
+fn merging_keeps_the_best_similarity_per_link() {
+    let output = |items: &[(&str, f32)]| Output {
+        original_url: String::new(),
+        items: items
+            .iter()
+            .map(|&(link, similarity)| Item {
+                link: link.to_owned(),
+                similarity,
+            })
+            .collect(),
+    };
+
+    let mut merged = merge(
+        "https://example.com/animated.gif".to_owned(),
+        vec![
+            output(&[
+                ("https://example.com/a", 60.0),
+                ("https://example.com/b", 90.0),
+            ]),
+            output(&[("https://example.com/a", 85.0)]),
+        ],
+    )
+    .items;
+    merged.sort_unstable_by(|a, b| a.link.cmp(&b.link));
+
+    assert_eq!(merged.len(), 2);
+    assert!((merged[0].similarity - 85.0).abs() < f32::EPSILON);
+    assert!((merged[1].similarity - 90.0).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+async fn animations_are_told_apart_by_their_content() {
+    let _harness = Harness::new().await;
+    // Named as if they were the other one, which shouldn't matter.
+    let animated = "https://example.com/frames-sniffed-animation.png";
+    let still = "https://example.com/frames-sniffed-still.gif";
+    stub_download(animated, gif(10));
+    stub_download(still, png());
+
+    let frames = frames::extract(animated)
+        .await
+        .expect("downloadable")
+        .expect("animated");
+    assert_eq!(frames.len(), frames::KEY_FRAMES);
+
+    assert!(
+        frames::extract(still)
+            .await
+            .expect("downloadable")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn oversized_downloads_are_given_up_on() {
+    let _harness = Harness::new().await;
+    let link = "https://example.com/frames-oversized.png";
+    stub_download(link, vec![0; 17 * 1024 * 1024]);
+
+    assert!(
+        frames::download(link)
+            .await
+            .expect("downloadable")
+            .is_none()
+    );
+}
+
+fn uploaded(requests: &[Request]) -> Vec<&str> {
+    requests
+        .iter()
+        .flat_map(|request| request.body["attachments"].as_array().into_iter().flatten())
+        .filter_map(|attachment| attachment["filename"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn animated_links_are_searched_frame_by_frame() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/frames-searched.gif";
+    stub_download(link, gif(10));
+
+    let requests = harness
+        .run(
+            "iqdb",
+            json!([{ "name": "link", "type": 3, "value": link }]),
+        )
+        .await;
+
+    let uploaded = uploaded(&requests);
+    assert_eq!(uploaded.len(), frames::KEY_FRAMES, "{requests:#?}");
+    assert!(
+        uploaded
+            .iter()
+            .all(|name| name.starts_with("SPOILER_frame-")),
+        "{uploaded:?}"
+    );
+}
+
+#[tokio::test]
+async fn frames_are_only_left_unspoilered_in_nsfw_channels() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/frames-nsfw.gif";
+    stub_download(link, gif(10));
+
+    let requests = harness
+        .run_nsfw(
+            "iqdb",
+            json!([{ "name": "link", "type": 3, "value": link }]),
+        )
+        .await;
+
+    let uploaded = uploaded(&requests);
+    assert_eq!(uploaded.len(), frames::KEY_FRAMES, "{requests:#?}");
+    assert!(
+        uploaded.iter().all(|name| name.starts_with("frame-")),
+        "{uploaded:?}"
+    );
+}
+
+#[tokio::test]
+async fn only_public_addresses_are_downloaded_from() {
+    for link in [
+        "http://127.0.0.1:1/frames.gif",
+        "http://[::1]:1/frames.gif",
+        "http://10.0.0.1:1/frames.gif",
+        "http://localhost:1/frames.gif",
+        "file:///etc/passwd",
+    ] {
+        assert!(HttpFetcher.open(link).await.is_err(), "{link}");
+    }
+}
+
+#[test]
+fn private_and_special_addresses_are_not_public() {
+    for ip in [
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+        IpAddr::V4(Ipv4Addr::new(169, 254, 169, 254)),
+        IpAddr::V4(Ipv4Addr::new(100, 64, 0, 1)),
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(Ipv6Addr::LOCALHOST),
+        IpAddr::V6(Ipv6Addr::new(0xFD00, 0, 0, 0, 0, 0, 0, 1)),
+        IpAddr::V6(Ipv6Addr::new(0xFE80, 0, 0, 0, 0, 0, 0, 1)),
+        IpAddr::V6(Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped()),
+    ] {
+        assert!(!is_public(ip), "{ip}");
+    }
+
+    assert!(is_public(IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34))));
+    assert!(is_public(IpAddr::V6(Ipv6Addr::new(
+        0x2606, 0x2800, 0x220, 1, 0, 0, 0, 0
+    ))));
+}
@@ -0,0 +1,527 @@
+//! Runs interactions against a local stand-in for the Discord REST API, with
+//! the search backends swapped out for stubs.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    net::SocketAddr,
+    process,
+    sync::{
+        Arc, LazyLock, Mutex, Once, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use sauce_api::source::{Item, Output};
+use serde_json::{Value, json};
+use sparkle_convenience::Bot;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use twilight_gateway::{EventTypeFlags, Intents};
+use twilight_http::Client;
+use twilight_model::{
+    application::interaction::{Interaction, InteractionContextType},
+    gateway::payload::incoming::InteractionCreate,
+    id::Id,
+};
+
+use crate::{
+    Res,
+    backend::{self, Backend, Searcher, SourceError},
+    config::Config,
+    events,
+    frames::{self, Body, Fetcher},
+    handle::{Handle, SpecialHandler as _},
+};
+
+pub const APPLICATION_ID: u64 = 2000;
+pub const CHANNEL_ID: u64 = 3000;
+pub const GUILD_ID: u64 = 4000;
+pub const OWNER_ID: u64 = 7000;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(10_000);
+
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A request the bot made to the mock Discord API.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// The path without the `/api/v10` prefix or the query string.
+    pub path: String,
+    pub body: Value,
+}
+
+pub struct Harness {
+    pub bot: Arc<Bot>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        install_stubs();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let addr = serve(requests.clone()).await;
+
+        let http = Client::builder()
+            .token("Bot test-token".to_owned())
+            .proxy(addr.to_string(), true)
+            .ratelimiter(None)
+            .build();
+
+        let bot = Bot {
+            http,
+            application: serde_json::from_value(application()).expect("valid application"),
+            user: serde_json::from_value(bot_user()).expect("valid user"),
+            intents: Intents::empty(),
+            event_type_flags: EventTypeFlags::INTERACTION_CREATE,
+            logging_channel_id: None,
+            logging_webhook: None,
+            logging_file_path: None,
+        };
+
+        Self {
+            bot: Arc::new(bot),
+            requests,
+        }
+    }
+
+    /// Runs a slash command as a new user, so cooldowns from other tests don't
+    /// get in the way, returning the requests it made.
+    pub async fn run(&self, name: &str, options: Value) -> Vec<Request> {
+        self.run_as(next_id(), name, options).await
+    }
+
+    pub async fn run_as(&self, user_id: u64, name: &str, options: Value) -> Vec<Request> {
+        self.send(command_interaction(2, user_id, name, options))
+            .await
+    }
+
+    /// Like [`Self::run`], but with the interaction changed by `tweak` first.
+    pub async fn run_with(
+        &self,
+        name: &str,
+        options: Value,
+        tweak: impl FnOnce(&mut Interaction),
+    ) -> Vec<Request> {
+        let mut interaction = command_interaction(2, next_id(), name, options);
+        tweak(&mut interaction);
+
+        self.send(interaction).await
+    }
+
+    /// Like [`Self::run`], but in an age-restricted channel.
+    pub async fn run_nsfw(&self, name: &str, options: Value) -> Vec<Request> {
+        self.run_with(name, options, |interaction| {
+            if let Some(channel) = &mut interaction.channel {
+                channel.nsfw = Some(true);
+            }
+        })
+        .await
+    }
+
+    /// Like [`Self::run`], but in a server the bot hasn't been added to, through
+    /// the user's own install of it.
+    pub async fn run_user_installed(&self, name: &str, options: Value) -> Vec<Request> {
+        self.run_with(name, options, |interaction| {
+            interaction.context = Some(InteractionContextType::Guild);
+            interaction.guild_id = Some(Id::new(GUILD_ID));
+            interaction.authorizing_integration_owners.user = interaction.author_id();
+        })
+        .await
+    }
+
+    /// Like [`Self::run`], but for a user with their Discord client set to
+    /// `locale`.
+    pub async fn run_in(&self, locale: &str, name: &str, options: Value) -> Vec<Request> {
+        self.run_with(name, options, |interaction| {
+            interaction.locale = Some(locale.to_owned());
+        })
+        .await
+    }
+
+    /// Asks for autocomplete choices as `user_id`, with `options` holding the
+    /// focused option.
+    pub async fn autocomplete_as(&self, user_id: u64, name: &str, options: Value) -> Vec<Request> {
+        self.send(command_interaction(4, user_id, name, options))
+            .await
+    }
+
+    /// A handle for a slash command interaction, for calling into the search
+    /// code directly.
+    pub fn handle(&self, name: &str) -> Handle {
+        self.bot
+            .handle(&command_interaction(2, next_id(), name, json!([])))
+    }
+
+    async fn send(&self, interaction: Interaction) -> Vec<Request> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+
+        events::interaction_create(self.bot.clone(), Box::new(InteractionCreate(interaction)))
+            .await
+            .expect("interaction handled");
+
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+fn command_interaction(kind: u8, user_id: u64, name: &str, options: Value) -> Interaction {
+    let id = next_id();
+
+    serde_json::from_value(json!({
+        "id": id.to_string(),
+        "application_id": APPLICATION_ID.to_string(),
+        "type": kind,
+        "token": format!("token-{id}"),
+        "channel": { "id": CHANNEL_ID.to_string(), "type": 0 },
+        "channel_id": CHANNEL_ID.to_string(),
+        "data": {
+            "id": next_id().to_string(),
+            "name": name,
+            "type": 1,
+            "options": options,
+        },
+        "user": user(user_id, "tester"),
+        "locale": "en-US",
+        "app_permissions": "2251799813685247",
+        "entitlements": [],
+        "authorizing_integration_owners": {},
+        "version": 1,
+    }))
+    .expect("valid interaction")
+}
+
+fn user(id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "discriminator": "0",
+        "avatar": null,
+    })
+}
+
+fn bot_user() -> Value {
+    json!({
+        "id": APPLICATION_ID.to_string(),
+        "username": "SauceBot",
+        "discriminator": "0",
+        "avatar": null,
+        "bot": true,
+        "mfa_enabled": false,
+        "verified": true,
+    })
+}
+
+fn application() -> Value {
+    json!({
+        "id": APPLICATION_ID.to_string(),
+        "name": "SauceBot",
+        "description": "",
+        "bot_public": true,
+        "bot_require_code_grant": false,
+        "icon": null,
+        "rpc_origins": [],
+        "verify_key": "00",
+        "approximate_guild_count": 42,
+        "owner": user(OWNER_ID, "owner"),
+    })
+}
+
+fn message() -> Value {
+    json!({
+        "id": next_id().to_string(),
+        "channel_id": CHANNEL_ID.to_string(),
+        "author": bot_user(),
+        "content": "",
+        "timestamp": "2024-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+/// The JSON part of a multipart upload, which lists the attachments as well.
+fn payload_json(body: &[u8]) -> Value {
+    let body = String::from_utf8_lossy(body);
+
+    body.split_once("name=\"payload_json\"")
+        .and_then(|(_, rest)| rest.split_once("\r\n\r\n"))
+        .and_then(|(_, rest)| rest.split_once("\r\n--"))
+        .and_then(|(json, _)| serde_json::from_str(json).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// A message with whatever `body` attached, hosted on Discord's CDN.
+fn message_with(body: &Value) -> Value {
+    let mut message = message();
+
+    message["attachments"] = body["attachments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|attachment| {
+            let id = next_id();
+            let filename = attachment["filename"].as_str().unwrap_or_default();
+            let url =
+                format!("https://cdn.discordapp.com/attachments/{CHANNEL_ID}/{id}/{filename}");
+
+            json!({
+                "id": id.to_string(),
+                "filename": filename,
+                "size": 0,
+                "url": url,
+                "proxy_url": url,
+            })
+        })
+        .collect();
+
+    message
+}
+
+/// Decides what the mock API answers, based on the route alone.
+fn route(method: &str, path: &str, body: &Value) -> (u16, Option<Value>) {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["interactions", _, _, "callback"]) => (204, None),
+        ("GET", ["applications", "@me"]) => (200, Some(application())),
+        ("POST", ["applications", _, "commands"]) => (
+            200,
+            Some(json!({
+                "id": next_id().to_string(),
+                "application_id": APPLICATION_ID.to_string(),
+                "name": body["name"],
+                "description": body["description"],
+                "type": 1,
+                "version": "1",
+                "options": [],
+            })),
+        ),
+        ("POST", ["webhooks", _, _]) | ("PATCH", ["webhooks", _, _, "messages", _]) => {
+            (200, Some(message_with(body)))
+        }
+        ("POST", ["channels", _, "messages"]) => (200, Some(message())),
+        _ => (404, Some(json!({ "message": "Unknown route", "code": 0 }))),
+    }
+}
+
+async fn serve(requests: Arc<Mutex<Vec<Request>>>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("able to bind");
+    let addr = listener.local_addr().expect("bound");
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(connection(stream, requests.clone()));
+        }
+    });
+
+    addr
+}
+
+/// Handles one keep-alive connection, one request at a time.
+async fn connection(stream: TcpStream, requests: Arc<Mutex<Vec<Request>>>) {
+    let mut stream = BufReader::new(stream);
+
+    loop {
+        let mut request_line = String::new();
+        if stream.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+            return;
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let target = parts.next().unwrap_or_default();
+        let path = target
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("/api/v10")
+            .to_owned();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if stream.read_line(&mut header).await.unwrap_or(0) == 0 {
+                return;
+            }
+
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        if stream.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let body = serde_json::from_slice(&body).unwrap_or_else(|_| payload_json(&body));
+
+        let (status, response) = route(&method, &path, &body);
+
+        requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Request { method, path, body });
+
+        let response = response.map(|v| v.to_string()).unwrap_or_default();
+        let head = format!(
+            "HTTP/1.1 {status} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+            response.len()
+        );
+
+        if stream.get_mut().write_all(head.as_bytes()).await.is_err()
+            || stream
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .is_err()
+        {
+            return;
+        }
+    }
+}
+
+static STUBBED: LazyLock<Mutex<HashMap<(Backend, String), Result<Output, String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Makes `backend` answer `link` with `items`. Links are shared between tests
+/// running in parallel, so every test should use its own.
+pub fn stub(backend: Backend, link: &str, items: &[(&str, f32)]) {
+    stub_result(
+        backend,
+        link,
+        Ok(Output {
+            original_url: link.to_owned(),
+            items: items
+                .iter()
+                .map(|&(link, similarity)| Item {
+                    link: link.to_owned(),
+                    similarity,
+                })
+                .collect(),
+        }),
+    );
+}
+
+/// Makes `backend` answer `link` with `result`, an error being replayed as
+/// its message.
+pub fn stub_result(backend: Backend, link: &str, result: Result<Output, String>) {
+    STUBBED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert((backend, link.to_owned()), result);
+}
+
+/// Stands in for a backend's [`Source`](sauce_api::source::Source), answering
+/// from whatever was registered with [`stub`].
+struct StubSource(Backend);
+
+#[async_trait]
+impl Searcher for StubSource {
+    async fn search(&self, link: &str) -> Result<Output, SourceError> {
+        let result = STUBBED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(self.0, link.to_owned()))
+            .cloned();
+
+        match result {
+            Some(Ok(output)) => Ok(output),
+            Some(Err(message)) => Err(message.into()),
+            None => Ok(Output {
+                original_url: link.to_owned(),
+                items: Vec::new(),
+            }),
+        }
+    }
+}
+
+static DOWNLOADS: LazyLock<Mutex<HashMap<String, Vec<u8>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Makes downloading `link` give `bytes`. Anything else fails to download.
+pub fn stub_download(link: &str, bytes: Vec<u8>) {
+    DOWNLOADS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(link.to_owned(), bytes);
+}
+
+/// Stands in for the web, answering from whatever was registered with
+/// [`stub_download`].
+struct StubFetcher;
+
+#[async_trait]
+impl Fetcher for StubFetcher {
+    async fn open(&self, link: &str) -> Res<Box<dyn Body>> {
+        let bytes = DOWNLOADS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(link)
+            .cloned()
+            .ok_or_else(|| eyre!("HTTP status client error (404 Not Found) for url ({link})"))?;
+
+        Ok(Box::new(StubBody(
+            bytes.chunks(4096).map(<[u8]>::to_vec).collect(),
+        )))
+    }
+}
+
+/// A body handed over a chunk at a time, without saying how big it is, like
+/// a chunked response.
+struct StubBody(VecDeque<Vec<u8>>);
+
+#[async_trait]
+impl Body for StubBody {
+    async fn chunk(&mut self) -> Res<Option<Vec<u8>>> {
+        Ok(self.0.pop_front())
+    }
+}
+
+fn install_stubs() {
+    static INSTALLED: Once = Once::new();
+
+    INSTALLED.call_once(|| {
+        // Keep `config.toml`, and everything kept next to it, out of the
+        // working directory.
+        let dir = env::temp_dir().join(format!("sauce-bot-tests-{}", process::id()));
+        fs::create_dir_all(&dir).expect("able to create a temporary directory");
+
+        Config::use_path(dir.join("config.toml"));
+        Config::set_current(Config::default());
+
+        for backend in Backend::ALL {
+            backend::set_override(backend, Arc::new(StubSource(backend)));
+        }
+
+        frames::set_fetcher(Arc::new(StubFetcher));
+    });
+}
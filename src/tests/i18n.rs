@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use twilight_interactions::command::CreateCommand;
+use twilight_model::application::command::CommandOption;
+
+use super::harness::{Harness, stub, stub_result};
+use crate::{backend::Backend, commands::iqdb::Iqdb, events, i18n};
+
+#[test]
+fn locales_fall_back_by_language_then_to_english() {
+    assert_eq!(i18n::text("de", "results-title", &[]), "Ergebnisse");
+    assert_eq!(i18n::text("de-AT", "results-title", &[]), "Ergebnisse");
+    assert_eq!(i18n::text("ja", "results-title", &[]), "Results");
+    assert_eq!(
+        i18n::text(
+            "en-GB",
+            "results-similarity",
+            &[("similarity", "93.50".into())]
+        ),
+        "Similarity: 93.50"
+    );
+}
+
+type Localizations<'a> = Option<&'a HashMap<String, String>>;
+
+fn german(localizations: Localizations<'_>) -> Option<&str> {
+    localizations?.get("de").map(String::as_str)
+}
+
+#[test]
+fn commands_are_registered_with_translations() {
+    let mut command = Iqdb::create_command();
+    i18n::localize(&mut command);
+
+    assert!(german(command.description_localizations.as_ref()).is_some());
+    assert!(german(command.name_localizations.as_ref()).is_none());
+
+    let preprocess = command
+        .options
+        .iter()
+        .find(|option| option.name == "preprocess")
+        .expect("a preprocess option");
+
+    assert_eq!(
+        german(preprocess.name_localizations.as_ref()),
+        Some("vorbereiten")
+    );
+    assert!(
+        preprocess
+            .choices
+            .iter()
+            .flatten()
+            .all(|choice| german(choice.name_localizations.as_ref()).is_some())
+    );
+}
+
+/// The description translations of every option under `parent`, nested ones
+/// included, by the option's full name.
+fn option_descriptions<'a>(
+    parent: &str,
+    options: &'a [CommandOption],
+    res: &mut Vec<(String, Localizations<'a>)>,
+) {
+    for option in options {
+        let name = format!("{parent} {}", option.name);
+
+        res.push((name.clone(), option.description_localizations.as_ref()));
+        option_descriptions(&name, option.options.as_deref().unwrap_or_default(), res);
+    }
+}
+
+#[test]
+fn every_command_is_described_in_every_locale() {
+    for (mut command, _) in events::commands() {
+        i18n::localize(&mut command);
+
+        let name = format!("/{}", command.name);
+        let mut descriptions = vec![(name.clone(), command.description_localizations.as_ref())];
+        option_descriptions(&name, &command.options, &mut descriptions);
+
+        for (name, localizations) in descriptions {
+            for locale in i18n::translated() {
+                assert!(
+                    localizations.is_some_and(|l| l.contains_key(locale)),
+                    "`{name}` has no {locale} description"
+                );
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn results_are_in_the_users_locale() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/localized-results.png";
+    stub(Backend::Iqdb, link, &[("https://example.com/found", 88.0)]);
+
+    let requests = harness
+        .run_in(
+            "de",
+            "iqdb",
+            json!([{ "name": "link", "type": 3, "value": link }]),
+        )
+        .await;
+
+    assert_eq!(requests.len(), 2, "{requests:#?}");
+
+    let embed = &requests[1].body["embeds"][0];
+    assert_eq!(embed["title"], "Ergebnisse");
+    assert_eq!(embed["fields"][0]["name"], "Ursprünglicher Link");
+    assert_eq!(embed["fields"][1]["name"], "Ähnlichkeit: 88.00");
+}
+
+#[tokio::test]
+async fn errors_are_in_the_users_locale() {
+    let harness = Harness::new().await;
+    let link = "https://example.com/localized-error.png";
+    stub_result(
+        Backend::FuzzySearch,
+        link,
+        Err("HTTP status client error (401 Unauthorized) for url (https://api-next.fuzzysearch.net/)".to_owned()),
+    );
+
+    let requests = harness
+        .run_in(
+            "de",
+            "fuzzysearch",
+            json!([{ "name": "link", "type": 3, "value": link }]),
+        )
+        .await;
+
+    let content = requests
+        .last()
+        .and_then(|request| request.body["content"].as_str())
+        .unwrap_or_default();
+    assert!(
+        content.starts_with("FuzzySearch: Das Such-Backend hat den API-Schlüssel"),
+        "{content}"
+    );
+    assert!(content.contains("Fehler-ID: `"), "{content}");
+}
+
+#[test]
+fn counts_pick_the_right_plural() {
+    let cooldown = |seconds: u64| {
+        i18n::text(
+            "en-US",
+            "cooldown",
+            &[("command", "iqdb".into()), ("seconds", seconds.into())],
+        )
+    };
+
+    assert!(cooldown(1).ends_with("in 1 second."), "{}", cooldown(1));
+    assert!(cooldown(5).ends_with("in 5 seconds."), "{}", cooldown(5));
+}
@@ -0,0 +1,10 @@
+mod canned;
+mod commands;
+mod config;
+mod endpoint;
+mod errors;
+mod frames;
+mod harness;
+mod i18n;
+mod preprocess;
+mod rendering;
@@ -0,0 +1,74 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use crate::preprocess::preprocess;
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Something busy enough that no line of it counts as plain.
+fn noise(x: u32, y: u32) -> Rgba<u8> {
+    let channel = |a: u32, b: u32| u8::try_from((x * a + y * b) % 200).unwrap_or_default();
+
+    Rgba([channel(37, 91), channel(13, 7), channel(59, 23), 255])
+}
+
+/// A white `size`-pixel square with noise wherever `busy` says.
+fn png(size: u32, busy: impl Fn(u32, u32) -> bool) -> Vec<u8> {
+    let image = RgbaImage::from_fn(
+        size,
+        size,
+        |x, y| {
+            if busy(x, y) { noise(x, y) } else { WHITE }
+        },
+    );
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .expect("encodable image");
+
+    png.into_inner()
+}
+
+fn dimensions(png: &[u8]) -> (u32, u32) {
+    image::load_from_memory(png)
+        .expect("valid png")
+        .to_rgba8()
+        .dimensions()
+}
+
+#[test]
+fn borders_are_cropped_off() {
+    let bordered = png(100, |x, y| (20..80).contains(&x) && (20..80).contains(&y));
+
+    let images = preprocess(&bordered, false).expect("decodable image");
+
+    assert_eq!(images.len(), 1);
+    assert_eq!(dimensions(&images[0]), (60, 60));
+}
+
+#[test]
+fn collages_are_split_into_tiles() {
+    let tile = |n: u32| (5..45).contains(&n) || (55..95).contains(&n);
+    let collage = png(100, |x, y| tile(x) && tile(y));
+
+    let images = preprocess(&collage, true).expect("decodable image");
+
+    assert_eq!(images.len(), 4);
+    for image in &images {
+        assert_eq!(dimensions(image), (40, 40));
+    }
+}
+
+#[test]
+fn nothing_changes_without_borders() {
+    let busy = png(50, |_, _| true);
+
+    assert!(
+        preprocess(&busy, false)
+            .expect("decodable image")
+            .is_empty()
+    );
+    assert!(preprocess(&busy, true).expect("decodable image").is_empty());
+}